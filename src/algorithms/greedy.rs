@@ -1,29 +1,33 @@
 use std::collections::HashMap;
 use std::time::Instant;
 
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
 
 use crate::data::instance::Instance;
-use crate::algorithms::utils::{NeighbourhoodGenerator, SearchAlgorithm, AlgorithmStepStatistics};
+use crate::algorithms::utils::{candidate_neighbourhood, get_move_distance, MoveType, NeighbourhoodGenerator, SearchAlgorithm, AlgorithmStepStatistics};
 
 use super::utils::Algorithm;
 
 pub struct GreedyLocalSearch {
     algorithm_name: String,
     instance: Instance,
-    initial_solution: Option<Vec<i32>>,
-    rng: ThreadRng,
+    initial_solution: Option<Vec<u32>>,
+    move_type: MoveType,
+    rng: StdRng,
     logging_interval: i32,
     log_history: HashMap<i32, AlgorithmStepStatistics>
 }
 
 impl NeighbourhoodGenerator for GreedyLocalSearch {
-    fn _generate_neighbourhood(&self) ->  Vec<(usize, usize)> {
+    fn _generate_neighbourhood(&self, current_solution: &[u32]) ->  Vec<(usize, usize)> {
+        if let Some(neighbourhood) = candidate_neighbourhood(self.move_type, &self.instance, current_solution) {
+            return neighbourhood;
+        }
         let mut neighbourhood = Vec::new();
-        for i in 0..self.instance.city_coords.len() {
-            for j in i+1..self.instance.city_coords.len() {
+        for i in 0..self.instance.dimension() {
+            for j in i+1..self.instance.dimension() {
                 neighbourhood.push((i, j));
             }
         }
@@ -33,11 +37,11 @@ impl NeighbourhoodGenerator for GreedyLocalSearch {
 
 
 impl SearchAlgorithm for GreedyLocalSearch {
-    fn _load_initial_solution(&mut self) -> Vec<i32> {
-        let current_solution: Vec<i32> = match &self.initial_solution {
+    fn _load_initial_solution(&mut self) -> Vec<u32> {
+        let current_solution: Vec<u32> = match &self.initial_solution {
             Some(solution) => solution.clone(),
             None => {
-                let mut sol: Vec<i32> = (1..(self.instance.city_coords.len() + 1) as i32).collect();
+                let mut sol: Vec<u32> = (0..self.instance.dimension() as u32).collect();
                 sol.shuffle(&mut self.rng);
                 sol
             },
@@ -46,14 +50,25 @@ impl SearchAlgorithm for GreedyLocalSearch {
     }
 }
 
+impl GreedyLocalSearch {
+    /// Uses `move_type` moves instead of swaps.
+    pub fn with_move_type(mut self, move_type: MoveType) -> Self {
+        let base_name = self.algorithm_name.trim_end_matches(self.move_type.name_suffix());
+        self.algorithm_name = format!("{}{}", base_name, move_type.name_suffix());
+        self.move_type = move_type;
+        self
+    }
+}
+
 impl Algorithm for GreedyLocalSearch {
-    fn new(instance: Instance, _steps: i32, logging_interval: i32, initial_solution: Option<Vec<i32>>) -> GreedyLocalSearch {
+    fn new(instance: Instance, _steps: i32, logging_interval: i32, initial_solution: Option<Vec<u32>>, seed: u64) -> GreedyLocalSearch {
         Self {
             algorithm_name: "G".to_string(),
             instance,
             logging_interval,
             initial_solution,
-            rng: thread_rng(),
+            move_type: MoveType::Swap,
+            rng: StdRng::seed_from_u64(seed),
             log_history: HashMap::new()
         }
     }
@@ -69,7 +84,7 @@ impl Algorithm for GreedyLocalSearch {
         let mut improved = true;
         let mut step = 0;
         let mut evaluated_solutions = 0;
-        let mut neighbourhood = self._generate_neighbourhood();
+        let mut neighbourhood = self._generate_neighbourhood(&current_solution);
 
         while improved {
             if step % self.logging_interval == 0 {
@@ -77,7 +92,8 @@ impl Algorithm for GreedyLocalSearch {
                     solution: current_solution.clone(),
                     solution_score: self.instance.get_solution_score(&current_solution),
                     solution_distance: self.instance.get_solution_distance(&current_solution),
-                    evaluated_solutions: evaluated_solutions,
+                    lower_bound_gap: self.instance.get_lower_bound_gap(&current_solution),
+                    evaluated_solutions,
                     elapsed_time: start_time.elapsed().as_micros()
                 };
                 self.log_history.insert(step, stats);
@@ -88,27 +104,29 @@ impl Algorithm for GreedyLocalSearch {
             
             for (index1, index2) in &neighbourhood {
                 evaluated_solutions += 1;
-                let mut neighbor_solution = current_solution.clone();
-                neighbor_solution.swap(*index1, *index2);
-                
-                let neighbor_distance = self.instance.get_solution_distance(&neighbor_solution);
-                
-                
+                let neighbor_distance = get_move_distance(self.move_type, *index1, *index2, &self.instance, &current_solution, current_distance);
+
                 if neighbor_distance < current_distance {
-                    current_solution = neighbor_solution;
+                    self.move_type.apply(&mut current_solution, *index1, *index2);
                     current_distance = neighbor_distance;
                     step += 1;
                     improved = true;
                     break;
                 }
             }
+
+            // Candidate moves follow the cities, so they change with every step
+            if improved && self.instance.candidate_lists().is_some() {
+                neighbourhood = self._generate_neighbourhood(&current_solution);
+            }
         }
 
         let stats = AlgorithmStepStatistics {
             solution: current_solution.clone(),
             solution_score: self.instance.get_solution_score(&current_solution),
             solution_distance: self.instance.get_solution_distance(&current_solution),
-            evaluated_solutions: evaluated_solutions,
+            lower_bound_gap: self.instance.get_lower_bound_gap(&current_solution),
+            evaluated_solutions,
             elapsed_time: start_time.elapsed().as_micros()
         };
         self.log_history.insert(step, stats);
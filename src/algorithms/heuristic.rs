@@ -2,8 +2,8 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use rand::Rng;
-use rand::rngs::ThreadRng;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 use crate::data::instance::Instance;
 use crate::algorithms::utils::{Algorithm, AlgorithmStepStatistics};
@@ -11,16 +11,16 @@ use crate::algorithms::utils::{Algorithm, AlgorithmStepStatistics};
 pub struct HeuristicBaseline {
     algorithm_name: String,
     instance: Instance,
-    rng: ThreadRng,
+    rng: StdRng,
     log_history: HashMap<i32, AlgorithmStepStatistics>
 }
 
 impl Algorithm for HeuristicBaseline {
-    fn new(instance: Instance, _steps: i32, _logging_interval: i32, _initial_solution: Option<Vec<i32>>) -> HeuristicBaseline {
+    fn new(instance: Instance, _steps: i32, _logging_interval: i32, _initial_solution: Option<Vec<u32>>, seed: u64) -> HeuristicBaseline {
         Self {
             algorithm_name: "H".to_string(),
             instance,
-            rng: thread_rng(),
+            rng: StdRng::seed_from_u64(seed),
             log_history: HashMap::new()
         }
     }
@@ -31,12 +31,12 @@ impl Algorithm for HeuristicBaseline {
 
     fn execute(&mut self) -> HashMap<i32, AlgorithmStepStatistics> {
         let start_time = Instant::now();
-        let num_cities = self.instance.city_coords.len();
+        let num_cities = self.instance.dimension();
         let mut visited = vec![false; num_cities];
-        let start_city = self.rng.gen_range(0..num_cities) as i32;
-        visited[start_city as usize] = true;
+        let start_city = self.rng.gen_range(0..num_cities);
+        visited[start_city] = true;
 
-        let mut tour = vec![start_city + 1];
+        let mut tour = vec![start_city as u32];
         let mut current_city = start_city;
 
         while tour.len() < num_cities {
@@ -48,7 +48,7 @@ impl Algorithm for HeuristicBaseline {
             let mut next_city = None;
 
             for &candidate_city in &candidates {
-                let distance = self.instance.adjacency_matrix[current_city as usize][candidate_city];
+                let distance = self.instance.distance(current_city, candidate_city);
                 if distance < min_distance {
                     min_distance = distance;
                     next_city = Some(candidate_city);
@@ -56,15 +56,16 @@ impl Algorithm for HeuristicBaseline {
             }
 
             if let Some(next_city) = next_city {
-                tour.push(next_city as i32 + 1);
+                tour.push(next_city as u32);
                 visited[next_city] = true;
-                current_city = next_city as i32;
+                current_city = next_city;
             }
         }
         let stats = AlgorithmStepStatistics {
             solution: tour.clone(),
             solution_score: self.instance.get_solution_score(&tour),
             solution_distance: self.instance.get_solution_distance(&tour),
+            lower_bound_gap: self.instance.get_lower_bound_gap(&tour),
             evaluated_solutions: 0,
             elapsed_time: start_time.elapsed().as_micros()
         };
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::time::Instant;
 
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
 
 use crate::data::instance::Instance;
 use crate::algorithms::utils::{Algorithm, SearchAlgorithm, AlgorithmStepStatistics};
@@ -13,18 +13,18 @@ pub struct RandomSearch {
     algorithm_name: String,
     instance: Instance,
     steps: i32,
-    initial_solution: Option<Vec<i32>>,
-    rng: ThreadRng,
+    initial_solution: Option<Vec<u32>>,
+    rng: StdRng,
     logging_interval: i32,
     log_history: HashMap<i32, AlgorithmStepStatistics>
 }
 
 impl SearchAlgorithm for RandomSearch {
-    fn _load_initial_solution(&mut self) -> Vec<i32> {
-        let current_solution: Vec<i32> = match &self.initial_solution {
+    fn _load_initial_solution(&mut self) -> Vec<u32> {
+        let current_solution: Vec<u32> = match &self.initial_solution {
             Some(solution) => solution.clone(),
             None => {
-                let mut sol: Vec<i32> = (1..(self.instance.city_coords.len() + 1) as i32).collect();
+                let mut sol: Vec<u32> = (0..self.instance.dimension() as u32).collect();
                 sol.shuffle(&mut self.rng);
                 sol
             },
@@ -34,14 +34,14 @@ impl SearchAlgorithm for RandomSearch {
 }
 
 impl Algorithm for RandomSearch {
-    fn new(instance: Instance, steps: i32, logging_interval: i32, initial_solution: Option<Vec<i32>>) -> RandomSearch {
+    fn new(instance: Instance, steps: i32, logging_interval: i32, initial_solution: Option<Vec<u32>>, seed: u64) -> RandomSearch {
         Self {
             algorithm_name: "RS".to_string(),
             instance,
             steps,
             logging_interval,
             initial_solution,
-            rng: thread_rng(),
+            rng: StdRng::seed_from_u64(seed),
             log_history: HashMap::new()
         }
     }
@@ -64,12 +64,13 @@ impl Algorithm for RandomSearch {
                     solution: current_solution.clone(),
                     solution_score: self.instance.get_solution_score(&current_solution),
                     solution_distance: self.instance.get_solution_distance(&current_solution),
-                    evaluated_solutions: evaluated_solutions,
+                    lower_bound_gap: self.instance.get_lower_bound_gap(&current_solution),
+                    evaluated_solutions,
                     elapsed_time: start_time.elapsed().as_micros()
                 };
                 self.log_history.insert(step, stats);
             }
-            let mut solution: Vec<i32> = (1..(self.instance.city_coords.len() + 1) as i32).collect();
+            let mut solution: Vec<u32> = (0..self.instance.dimension() as u32).collect();
             solution.shuffle(&mut self.rng);
             let distance = self.instance.get_solution_distance(&solution);
             if distance < current_distance {
@@ -83,7 +84,8 @@ impl Algorithm for RandomSearch {
             solution: current_solution.clone(),
             solution_score: self.instance.get_solution_score(&current_solution),
             solution_distance: self.instance.get_solution_distance(&current_solution),
-            evaluated_solutions: evaluated_solutions,
+            lower_bound_gap: self.instance.get_lower_bound_gap(&current_solution),
+            evaluated_solutions,
             elapsed_time: start_time.elapsed().as_micros()
         };
         self.log_history.insert(self.steps, stats);
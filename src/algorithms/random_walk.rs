@@ -2,30 +2,30 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use rand::Rng;
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
 
 use crate::data::instance::Instance;
-use crate::algorithms::utils::{Algorithm, SearchAlgorithm, AlgorithmStepStatistics};
+use crate::algorithms::utils::{get_move_distance, MoveType, Algorithm, SearchAlgorithm, AlgorithmStepStatistics};
 
 
 pub struct RandomWalk {
     algorithm_name: String,
     instance: Instance,
     steps: i32,
-    initial_solution: Option<Vec<i32>>,
-    rng: ThreadRng,
+    initial_solution: Option<Vec<u32>>,
+    rng: StdRng,
     logging_interval: i32,
     log_history: HashMap<i32, AlgorithmStepStatistics>
 }
 
 impl SearchAlgorithm for RandomWalk {
-    fn _load_initial_solution(&mut self) -> Vec<i32> {
-        let current_solution: Vec<i32> = match &self.initial_solution {
+    fn _load_initial_solution(&mut self) -> Vec<u32> {
+        let current_solution: Vec<u32> = match &self.initial_solution {
             Some(solution) => solution.clone(),
             None => {
-                let mut sol: Vec<i32> = (1..(self.instance.city_coords.len() + 1) as i32).collect();
+                let mut sol: Vec<u32> = (0..self.instance.dimension() as u32).collect();
                 sol.shuffle(&mut self.rng);
                 sol
             },
@@ -35,14 +35,14 @@ impl SearchAlgorithm for RandomWalk {
 }
 
 impl Algorithm for RandomWalk {
-    fn new(instance: Instance, steps: i32, logging_interval: i32, initial_solution: Option<Vec<i32>>) -> RandomWalk {
+    fn new(instance: Instance, steps: i32, logging_interval: i32, initial_solution: Option<Vec<u32>>, seed: u64) -> RandomWalk {
         Self {
             algorithm_name: "RW".to_string(),
             instance,
             steps,
             logging_interval,
             initial_solution,
-            rng: thread_rng(),
+            rng: StdRng::seed_from_u64(seed),
             log_history: HashMap::new()
         }
     }
@@ -65,20 +65,18 @@ impl Algorithm for RandomWalk {
                     solution: current_solution.clone(),
                     solution_score: self.instance.get_solution_score(&current_solution),
                     solution_distance: self.instance.get_solution_distance(&current_solution),
-                    evaluated_solutions: evaluated_solutions,
+                    lower_bound_gap: self.instance.get_lower_bound_gap(&current_solution),
+                    evaluated_solutions,
                     elapsed_time: start_time.elapsed().as_micros()
                 };
                 self.log_history.insert(step, stats);
             }
-            let mut neighbor_solution = current_solution.clone();
-            let index1 = self.rng.gen_range(0..neighbor_solution.len());
-            let index2 = self.rng.gen_range(0..neighbor_solution.len());
-            neighbor_solution.swap(index1, index2);
-            
-            let neighbor_distance = self.instance.get_solution_distance(&neighbor_solution);
+            let index1 = self.rng.gen_range(0..current_solution.len());
+            let index2 = self.rng.gen_range(0..current_solution.len());
+            let neighbor_distance = get_move_distance(MoveType::Swap, index1, index2, &self.instance, &current_solution, current_distance);
             
             if neighbor_distance < current_distance {
-                current_solution = neighbor_solution;
+                current_solution.swap(index1, index2);
                 current_distance = neighbor_distance;
                 step += 1;
             }
@@ -88,7 +86,8 @@ impl Algorithm for RandomWalk {
             solution: current_solution.clone(),
             solution_score: self.instance.get_solution_score(&current_solution),
             solution_distance: self.instance.get_solution_distance(&current_solution),
-            evaluated_solutions: evaluated_solutions,
+            lower_bound_gap: self.instance.get_lower_bound_gap(&current_solution),
+            evaluated_solutions,
             elapsed_time: start_time.elapsed().as_micros()
         };
         self.log_history.insert(self.steps, stats);
@@ -4,18 +4,48 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use crate::data::instance::Instance;
-use crate::algorithms::utils::{Algorithm, SearchAlgorithm, AlgorithmStepStatistics, NeighbourhoodGenerator};
+use crate::algorithms::utils::{candidate_city_pairs, candidate_neighbourhood, city_positions, get_move_distance, moves_towards, MoveType, Algorithm, SearchAlgorithm, AlgorithmStepStatistics, NeighbourhoodGenerator};
 
 pub struct SimmulatedAnnealing {
     algorithm_name: String,
     instance: Instance,
-    initial_solution: Option<Vec<i32>>,
-    rng: ThreadRng,
+    initial_solution: Option<Vec<u32>>,
+    move_type: MoveType,
+    rng: StdRng,
     logging_interval: i32,
     log_history: HashMap<i32, AlgorithmStepStatistics>
 }
 
 impl SimmulatedAnnealing {
+    /// Uses `move_type` moves instead of swaps.
+    pub fn with_move_type(mut self, move_type: MoveType) -> Self {
+        let base_name = self.algorithm_name.trim_end_matches(self.move_type.name_suffix());
+        self.algorithm_name = format!("{}{}", base_name, move_type.name_suffix());
+        self.move_type = move_type;
+        self
+    }
+
+    // Random move bringing a random city next to one of its candidates where
+    // they currently lie, or of the neighbourhood without candidate lists or
+    // when both cities are already adjacent
+    fn sample_move(
+        &mut self,
+        neighbourhood: &[(usize, usize)],
+        candidate_pairs: Option<&[(u32, u32)]>,
+        positions: &[usize],
+    ) -> (usize, usize) {
+        if let Some(&(city, neighbour)) = candidate_pairs.and_then(|pairs| pairs.choose(&mut self.rng)) {
+            let candidate_move = match moves_towards(self.move_type, positions, city, neighbour) {
+                [Some(first), Some(second)] => Some(if self.rng.gen() { first } else { second }),
+                [first, second] => first.or(second),
+            };
+            if let Some(candidate_move) = candidate_move {
+                return candidate_move;
+            }
+        }
+        *neighbourhood.choose(&mut self.rng).expect("Neighbourhood error!")
+    }
+
     fn acceptance_probability(&mut self, current_energy: f64, new_energy: f64, temperature: f64) -> f64 {
         if new_energy < current_energy {
             1.0
@@ -26,11 +56,11 @@ impl SimmulatedAnnealing {
 }
 
 impl SearchAlgorithm for SimmulatedAnnealing {
-    fn _load_initial_solution(&mut self) -> Vec<i32> {
-        let current_solution: Vec<i32> = match &self.initial_solution {
+    fn _load_initial_solution(&mut self) -> Vec<u32> {
+        let current_solution: Vec<u32> = match &self.initial_solution {
             Some(solution) => solution.clone(),
             None => {
-                let mut sol: Vec<i32> = (1..(self.instance.city_coords.len() + 1) as i32).collect();
+                let mut sol: Vec<u32> = (0..self.instance.dimension() as u32).collect();
                 sol.shuffle(&mut self.rng);
                 sol
             },
@@ -40,10 +70,13 @@ impl SearchAlgorithm for SimmulatedAnnealing {
 }
 
 impl NeighbourhoodGenerator for SimmulatedAnnealing {
-    fn _generate_neighbourhood(&self) ->  Vec<(usize, usize)> {
+    fn _generate_neighbourhood(&self, current_solution: &[u32]) ->  Vec<(usize, usize)> {
+        if let Some(neighbourhood) = candidate_neighbourhood(self.move_type, &self.instance, current_solution) {
+            return neighbourhood;
+        }
         let mut neighbourhood = Vec::new();
-        for i in 0..self.instance.city_coords.len() {
-            for j in i+1..self.instance.city_coords.len() {
+        for i in 0..self.instance.dimension() {
+            for j in i+1..self.instance.dimension() {
                 neighbourhood.push((i, j));
             }
         }
@@ -52,13 +85,14 @@ impl NeighbourhoodGenerator for SimmulatedAnnealing {
 }
 
 impl Algorithm for SimmulatedAnnealing {
-    fn new(instance: Instance, _steps: i32, logging_interval: i32, initial_solution: Option<Vec<i32>>) -> SimmulatedAnnealing {
+    fn new(instance: Instance, _steps: i32, logging_interval: i32, initial_solution: Option<Vec<u32>>, seed: u64) -> SimmulatedAnnealing {
         Self {
             algorithm_name: "SA".to_string(),
             instance,
             logging_interval,
             initial_solution,
-            rng: thread_rng(),
+            move_type: MoveType::Swap,
+            rng: StdRng::seed_from_u64(seed),
             log_history: HashMap::new()
         }
     }
@@ -69,13 +103,15 @@ impl Algorithm for SimmulatedAnnealing {
 
     fn execute(&mut self) -> HashMap<i32, AlgorithmStepStatistics> {
         let start_time = Instant::now();
-        let neighbourhood: Vec<(usize, usize)> = self._generate_neighbourhood();
-    
         let mut current_solution = self._load_initial_solution();
         let mut current_distance = self.instance.get_solution_distance(&current_solution);
+        let neighbourhood: Vec<(usize, usize)> = self._generate_neighbourhood(&current_solution);
+        // Candidate moves are drawn as city pairs, and turned into positions when sampled
+        let candidate_pairs = candidate_city_pairs(&self.instance);
+        let mut positions = city_positions(&current_solution);
 
         let mut best_solution = current_solution.clone();
-        let mut best_distance = current_distance.clone();
+        let mut best_distance = current_distance;
 
         let mut step = 0;
         let mut evaluated_solutions = 0;
@@ -91,14 +127,12 @@ impl Algorithm for SimmulatedAnnealing {
         loop {
             let mut accepted_solutions: usize = 0;
             for _ in 0..max_iterations {
-                let mut new_solution = current_solution.clone();
-                let (swap_index1, swap_index2) = neighbourhood.choose(&mut self.rng).expect("Neighbourhood error!");
-                new_solution.swap(*swap_index1, *swap_index2);
-                let new_distance = self.instance.get_solution_distance(&new_solution);
+                let (swap_index1, swap_index2) = self.sample_move(&neighbourhood, candidate_pairs.as_deref(), &positions);
+                let new_distance = get_move_distance(self.move_type, swap_index1, swap_index2, &self.instance, &current_solution, current_distance);
                 
                 if self.acceptance_probability(current_distance, new_distance, temp) > self.rng.gen() {
                     accepted_solutions += 1;
-                    current_solution = new_solution;
+                    self.move_type.apply_with_positions(&mut current_solution, &mut positions, swap_index1, swap_index2);
                     current_distance = new_distance;
                 }
             }
@@ -118,29 +152,27 @@ impl Algorithm for SimmulatedAnnealing {
                     solution: best_solution.clone(),
                     solution_score: self.instance.get_solution_score(&best_solution),
                     solution_distance: self.instance.get_solution_distance(&best_solution),
-                    evaluated_solutions: evaluated_solutions,
+                    lower_bound_gap: self.instance.get_lower_bound_gap(&best_solution),
+                    evaluated_solutions,
                     elapsed_time: start_time.elapsed().as_micros()
                 };
                 self.log_history.insert(step, stats);
             }
 
             for _ in 0..max_iterations{
-                let mut new_solution = current_solution.clone();
-                let (swap_index1, swap_index2) = neighbourhood.choose(&mut self.rng).expect("Neighbourhood error!");
-                new_solution.swap(*swap_index1, *swap_index2);
-
-                let new_distance = self.instance.get_solution_distance(&new_solution);
+                let (swap_index1, swap_index2) = self.sample_move(&neighbourhood, candidate_pairs.as_deref(), &positions);
+                let new_distance = get_move_distance(self.move_type, swap_index1, swap_index2, &self.instance, &current_solution, current_distance);
                 evaluated_solutions += 1;
 
                 if self.acceptance_probability(current_distance, new_distance, temp) > self.rng.gen() {
-                    current_solution = new_solution;
+                    self.move_type.apply_with_positions(&mut current_solution, &mut positions, swap_index1, swap_index2);
                     current_distance = new_distance;
                     step += 1;
                 }
 
                 if current_distance < best_distance {
                     best_solution = current_solution.clone();
-                    best_distance = current_distance.clone();
+                    best_distance = current_distance;
                     no_improvement_counter = 0;
                 }
                 else {
@@ -158,7 +190,8 @@ impl Algorithm for SimmulatedAnnealing {
             solution: best_solution.clone(),
             solution_score: self.instance.get_solution_score(&best_solution),
             solution_distance: self.instance.get_solution_distance(&best_solution),
-            evaluated_solutions: evaluated_solutions,
+            lower_bound_gap: self.instance.get_lower_bound_gap(&best_solution),
+            evaluated_solutions,
             elapsed_time: start_time.elapsed().as_micros()
         };
         self.log_history.insert(step, stats);
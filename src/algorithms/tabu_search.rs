@@ -3,29 +3,29 @@ use std::cmp::Ordering;
 use std::collections::VecDeque;
 
 use std::collections::HashMap;
-use std::f64::INFINITY;
 use std::time::Instant;
 
 use crate::data::instance::Instance;
-use crate::algorithms::utils::{Algorithm, SearchAlgorithm, AlgorithmStepStatistics, NeighbourhoodGenerator};
+use crate::algorithms::utils::{candidate_neighbourhood, get_move_distance, MoveType, Algorithm, SearchAlgorithm, AlgorithmStepStatistics, NeighbourhoodGenerator};
 
 
 pub struct TabuSearch {
     algorithm_name: String,
     instance: Instance,
-    initial_solution: Option<Vec<i32>>,
-    rng: ThreadRng,
+    initial_solution: Option<Vec<u32>>,
+    move_type: MoveType,
+    rng: StdRng,
     logging_interval: i32,
     log_history: HashMap<i32, AlgorithmStepStatistics>
 }
 
 
 impl SearchAlgorithm for TabuSearch {
-    fn _load_initial_solution(&mut self) -> Vec<i32> {
-        let current_solution: Vec<i32> = match &self.initial_solution {
+    fn _load_initial_solution(&mut self) -> Vec<u32> {
+        let current_solution: Vec<u32> = match &self.initial_solution {
             Some(solution) => solution.clone(),
             None => {
-                let mut sol: Vec<i32> = (1..(self.instance.city_coords.len() + 1) as i32).collect();
+                let mut sol: Vec<u32> = (0..self.instance.dimension() as u32).collect();
                 sol.shuffle(&mut self.rng);
                 sol
             },
@@ -35,10 +35,13 @@ impl SearchAlgorithm for TabuSearch {
 }
 
 impl NeighbourhoodGenerator for TabuSearch {
-    fn _generate_neighbourhood(&self) ->  Vec<(usize, usize)> {
+    fn _generate_neighbourhood(&self, current_solution: &[u32]) ->  Vec<(usize, usize)> {
+        if let Some(neighbourhood) = candidate_neighbourhood(self.move_type, &self.instance, current_solution) {
+            return neighbourhood;
+        }
         let mut neighbourhood = Vec::new();
-        for i in 0..self.instance.city_coords.len() {
-            for j in i+1..self.instance.city_coords.len() {
+        for i in 0..self.instance.dimension() {
+            for j in i+1..self.instance.dimension() {
                 neighbourhood.push((i, j));
             }
         }
@@ -46,14 +49,25 @@ impl NeighbourhoodGenerator for TabuSearch {
     }
 }
 
+impl TabuSearch {
+    /// Uses `move_type` moves instead of swaps.
+    pub fn with_move_type(mut self, move_type: MoveType) -> Self {
+        let base_name = self.algorithm_name.trim_end_matches(self.move_type.name_suffix());
+        self.algorithm_name = format!("{}{}", base_name, move_type.name_suffix());
+        self.move_type = move_type;
+        self
+    }
+}
+
 impl Algorithm for TabuSearch {
-    fn new(instance: Instance, _steps: i32, logging_interval: i32, initial_solution: Option<Vec<i32>>) -> TabuSearch {
+    fn new(instance: Instance, _steps: i32, logging_interval: i32, initial_solution: Option<Vec<u32>>, seed: u64) -> TabuSearch {
         Self {
             algorithm_name: "TS".to_string(),
             instance,
             logging_interval,
             initial_solution,
-            rng: thread_rng(),
+            move_type: MoveType::Swap,
+            rng: StdRng::seed_from_u64(seed),
             log_history: HashMap::new()
         }
     }
@@ -64,25 +78,27 @@ impl Algorithm for TabuSearch {
 
     fn execute(&mut self) -> HashMap<i32, AlgorithmStepStatistics> {
         let start_time = Instant::now();
-        let neighbourhood = self._generate_neighbourhood();
-
         let mut current_solution = self._load_initial_solution();
         let mut current_distance = self.instance.get_solution_distance(&current_solution);
+        let mut neighbourhood = self._generate_neighbourhood(&current_solution);
 
         let mut best_solution = current_solution.clone();
-        let mut best_distance = current_distance.clone();
+        let mut best_distance = current_distance;
 
         let mut evaluated_solutions: i32 = 0;
         let mut no_improvement_counter: i32 = 0;
         let mut step = 0;
 
         // Initialize tabu specific parameters
-        let tabu_tenure = self.instance.city_coords.len() / 4;
+        // Both at least one, so that small instances still get a move to make and forget
+        let tabu_tenure = (self.instance.dimension() / 4).max(1);
         let mut tabu_list: HashMap<(usize, usize), usize> = HashMap::new(); // Move as a key and current tenure as value
 
         let mut master_list: VecDeque<((usize, usize), f64)> = VecDeque::new(); // Move as first element and distance as second
-        let elite_k = self.instance.city_coords.len() / 10;
-        let mut master_list_threshold: f64 = INFINITY;
+        let elite_k = (self.instance.dimension() / 10).max(1);
+        let mut master_list_threshold: f64 = f64::INFINITY;
+        // Candidate moves go stale once cities move, so the master list is rebuilt after every move
+        let refresh_neighbourhood = self.instance.candidate_lists().is_some();
 
         loop {
             if step % self.logging_interval == 0 {
@@ -90,7 +106,8 @@ impl Algorithm for TabuSearch {
                     solution: best_solution.clone(),
                     solution_score: self.instance.get_solution_score(&best_solution),
                     solution_distance: self.instance.get_solution_distance(&best_solution),
-                    evaluated_solutions: evaluated_solutions,
+                    lower_bound_gap: self.instance.get_lower_bound_gap(&best_solution),
+                    evaluated_solutions,
                     elapsed_time: start_time.elapsed().as_micros()
                 };
                 self.log_history.insert(step, stats);
@@ -99,11 +116,13 @@ impl Algorithm for TabuSearch {
             // Construct master list 
             if master_list.is_empty(){
                 // If its empty, evaluate whole neighbourhood and take the elite
+                // Candidate moves follow the cities, so they are rebuilt for the current solution
+                if refresh_neighbourhood {
+                    neighbourhood = self._generate_neighbourhood(&current_solution);
+                }
                 let mut temp_master_list: Vec<((usize, usize), f64)> = neighbourhood.iter()
                 .map(|&move_| {
-                    let mut move_solution: Vec<i32> = current_solution.clone();
-                    move_solution.swap(move_.0, move_.1);
-                    let distance = self.instance.get_solution_distance(&move_solution);
+                    let distance = get_move_distance(self.move_type, move_.0, move_.1, &self.instance, &current_solution, current_distance);
                     (move_, distance)
                 }).collect();
                 evaluated_solutions += neighbourhood.len() as i32;
@@ -115,9 +134,7 @@ impl Algorithm for TabuSearch {
                 // If elite candidates exists, re-evaluate only them
                 let mut temp_master_list: Vec<((usize, usize), f64)> = Vec::from(master_list).iter()
                 .map(|&(move_, _)| {
-                    let mut move_solution: Vec<i32> = current_solution.clone();
-                    move_solution.swap(move_.0, move_.1);
-                    let distance = self.instance.get_solution_distance(&move_solution);
+                    let distance = get_move_distance(self.move_type, move_.0, move_.1, &self.instance, &current_solution, current_distance);
                     (move_, distance)
                 }).collect();
                 evaluated_solutions += temp_master_list.len() as i32;
@@ -137,7 +154,7 @@ impl Algorithm for TabuSearch {
             }
             // Remove decayed move from tabu list
             let decayed_moves: Vec<(usize, usize)> = tabu_list.iter()
-                .filter(|&(_, &value)| value <= 0)
+                .filter(|&(_, &value)| value == 0)
                 .map(|(key, _)| *key)
                 .collect();
             for _move in decayed_moves {
@@ -147,12 +164,13 @@ impl Algorithm for TabuSearch {
             let (master_move, candidate_distance)= master_list.pop_front().expect("");
             if !tabu_list.contains_key(&master_move) || candidate_distance < best_distance{
                 // If move is not tabu or has best distance ever found then use it
-                let mut candidate = current_solution.clone();
-                candidate.swap(master_move.0, master_move.1);
-                current_solution = candidate;
+                self.move_type.apply(&mut current_solution, master_move.0, master_move.1);
                 current_distance = candidate_distance;
                 tabu_list.insert(master_move, tabu_tenure);
                 step += 1;
+                if refresh_neighbourhood {
+                    master_list.clear();
+                }
 
                 if current_distance < best_distance {
                     best_solution = current_solution.clone();
@@ -171,7 +189,8 @@ impl Algorithm for TabuSearch {
             solution: best_solution.clone(),
             solution_score: self.instance.get_solution_score(&best_solution),
             solution_distance: self.instance.get_solution_distance(&best_solution),
-            evaluated_solutions: evaluated_solutions,
+            lower_bound_gap: self.instance.get_lower_bound_gap(&best_solution),
+            evaluated_solutions,
             elapsed_time: start_time.elapsed().as_micros()
         };
         self.log_history.insert(step, stats);
@@ -1,32 +1,154 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::data::instance::Instance;
 
 
 pub trait Algorithm {
-    fn new(instance: Instance, steps: i32, logging_interval: i32, initial_solution: Option<Vec<i32>>) -> Self where Self: Sized;
+    /// The same seed reproduces the same run.
+    fn new(instance: Instance, steps: i32, logging_interval: i32, initial_solution: Option<Vec<u32>>, seed: u64) -> Self where Self: Sized;
     fn execute(&mut self) -> HashMap<i32, AlgorithmStepStatistics>;
     fn get_name(&self) -> &String;
 }
 pub trait SearchAlgorithm {
-    fn _load_initial_solution(&mut self) -> Vec<i32>;
+    fn _load_initial_solution(&mut self) -> Vec<u32>;
 }
 
 pub trait NeighbourhoodGenerator: SearchAlgorithm {
-    fn _generate_neighbourhood(&self) ->  Vec<(usize, usize)>;
+    fn _generate_neighbourhood(&self, current_solution: &[u32]) ->  Vec<(usize, usize)>;
 }
 
 #[derive(Clone)]
 pub struct AlgorithmStepStatistics {
-    pub solution: Vec<i32>,
+    pub solution: Vec<u32>,
     pub solution_score: f64,
     pub solution_distance: f64,
+    pub lower_bound_gap: f64,
     pub evaluated_solutions: i32,
     pub elapsed_time: u128,
 }
 
 
-pub fn get_move_distance(a: usize, b: usize, instance: &Instance, current_solution: &Vec<i32>) -> f64 {
-    let mut a_candidate = current_solution.clone();
-    a_candidate.swap(a, b);
-    instance.get_solution_distance(&a_candidate)
+/// Neighbourhood move between two tour positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MoveType {
+    /// Exchange the cities at both positions.
+    #[default]
+    Swap,
+    /// Reverse the segment between both positions (inclusive). Segments
+    /// wrapping around the end of the tour are not reversible this way.
+    TwoOpt,
+}
+
+impl MoveType {
+    pub fn apply(&self, solution: &mut [u32], a: usize, b: usize) {
+        match self {
+            MoveType::Swap => solution.swap(a, b),
+            MoveType::TwoOpt => solution[a.min(b)..=a.max(b)].reverse(),
+        }
+    }
+
+    /// Applies the move and updates the tour position of every moved city.
+    pub fn apply_with_positions(&self, solution: &mut [u32], positions: &mut [usize], a: usize, b: usize) {
+        self.apply(solution, a, b);
+        let mut update = |position: usize| positions[solution[position] as usize] = position;
+        match self {
+            MoveType::Swap => [a, b].into_iter().for_each(&mut update),
+            MoveType::TwoOpt => (a.min(b)..=a.max(b)).for_each(&mut update),
+        }
+    }
+
+    /// Suffix telling algorithms apart by move type in results, empty for swaps.
+    pub fn name_suffix(&self) -> &'static str {
+        match self {
+            MoveType::Swap => "",
+            MoveType::TwoOpt => "-2opt",
+        }
+    }
+}
+
+// Length of the tour after applying the move at positions a and b, from the
+// delta evaluation. Debug builds cross-check it against a full evaluation.
+pub fn get_move_distance(
+    move_type: MoveType,
+    a: usize,
+    b: usize,
+    instance: &Instance,
+    current_solution: &[u32],
+    current_distance: f64,
+) -> f64 {
+    let delta = match move_type {
+        MoveType::Swap => instance.swap_delta(current_solution, a, b),
+        MoveType::TwoOpt => instance.reversal_delta(current_solution, a, b),
+    };
+    let distance = current_distance + delta;
+    debug_assert!({
+        let mut a_candidate = current_solution.to_vec();
+        move_type.apply(&mut a_candidate, a, b);
+        let full_distance = instance.get_solution_distance(&a_candidate);
+        (distance - full_distance).abs() <= 1e-6 * full_distance.abs().max(1.0)
+    }, "Delta evaluation of the {:?} move ({}, {}) disagrees with the full evaluation", move_type, a, b);
+    distance
+}
+
+
+// Tour position of every city.
+pub fn city_positions(solution: &[u32]) -> Vec<usize> {
+    let mut positions = vec![0; solution.len()];
+    for (position, &city) in solution.iter().enumerate() {
+        positions[city as usize] = position;
+    }
+    positions
+}
+
+// Every (city, candidate neighbour) pair, or None without candidate lists.
+// Unlike position moves, these stay meaningful as the solution changes.
+pub fn candidate_city_pairs(instance: &Instance) -> Option<Vec<(u32, u32)>> {
+    let candidates = instance.candidate_lists()?;
+    let pairs: Vec<(u32, u32)> = candidates.as_slice().iter()
+        .enumerate()
+        .flat_map(|(city, neighbours)| neighbours.iter().map(move |&neighbour| (city as u32, neighbour)))
+        .collect();
+    (!pairs.is_empty()).then_some(pairs)
+}
+
+// Moves bringing `city` next to `neighbour`, as (smaller, larger) positions
+// given the current `positions` of the cities. 2-opt moves only reverse the
+// segment between both positions, never the one wrapping around the end of
+// the tour: on symmetric instances both give the same tour, on asymmetric ones
+// the wrapped reversal, which flips the other arcs, is not tried.
+pub fn moves_towards(move_type: MoveType, positions: &[usize], city: u32, neighbour: u32) -> [Option<(usize, usize)>; 2] {
+    let n = positions.len();
+    let (position, neighbour_position) = (positions[city as usize], positions[neighbour as usize]);
+    match move_type {
+        // Move the city next to its neighbour, on either side
+        MoveType::Swap => [(neighbour_position + n - 1) % n, (neighbour_position + 1) % n].map(|target| {
+            (target != position).then(|| (position.min(target), position.max(target)))
+        }),
+        // Reverse what lies between them, with either end of the span
+        MoveType::TwoOpt => {
+            let (i, j) = (position.min(neighbour_position), position.max(neighbour_position));
+            if i + 1 < j { [Some((i + 1, j)), Some((i, j - 1))] } else { [None, None] }
+        }
+    }
+}
+
+// Moves that bring every city next to one of its candidate neighbours, as
+// (smaller, larger) positions, or None without candidate lists or when they
+// yield no move. See `moves_towards` for the 2-opt moves left out. The moves depend on where the cities are, so they go stale
+// as the solution changes.
+pub fn candidate_neighbourhood(
+    move_type: MoveType,
+    instance: &Instance,
+    current_solution: &[u32],
+) -> Option<Vec<(usize, usize)>> {
+    let candidates = instance.candidate_lists()?;
+    let positions = city_positions(current_solution);
+    let mut neighbourhood = HashSet::new();
+    for &city in current_solution {
+        for &neighbour in candidates.neighbours(city as usize) {
+            neighbourhood.extend(moves_towards(move_type, &positions, city, neighbour).into_iter().flatten());
+        }
+    }
+    let mut neighbourhood: Vec<(usize, usize)> = neighbourhood.into_iter().collect();
+    neighbourhood.sort_unstable();
+    (!neighbourhood.is_empty()).then_some(neighbourhood)
 }
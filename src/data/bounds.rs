@@ -0,0 +1,101 @@
+use crate::data::distances::DistanceProvider;
+use crate::data::one_tree;
+
+/// Lower bound on any tour length: every city is entered and left through
+/// its two cheapest edges at best, and each edge is counted at both ends.
+pub fn two_cheapest_edges_bound<D: DistanceProvider + ?Sized>(distances: &D) -> f64 {
+    partial_two_cheapest_edges_bound(distances, distances.dimension())
+}
+
+/// `two_cheapest_edges_bound` summed over the first `cities` cities only, which
+/// is still a lower bound as every city adds a non-negative amount, for
+/// `cities × n` distance lookups.
+pub fn partial_two_cheapest_edges_bound<D: DistanceProvider + ?Sized>(distances: &D, cities: usize) -> f64 {
+    let mut bound = 0.0;
+    for i in 0..cities.min(distances.dimension()) {
+        let mut cheapest = f64::INFINITY;
+        let mut second_cheapest = f64::INFINITY;
+        for j in 0..distances.dimension() {
+            if i == j {
+                continue;
+            }
+            let distance = distances.distance(i, j);
+            if distance < cheapest {
+                second_cheapest = cheapest;
+                cheapest = distance;
+            } else if distance < second_cheapest {
+                second_cheapest = distance;
+            }
+        }
+        if second_cheapest.is_finite() {
+            bound += (cheapest + second_cheapest) / 2.0;
+        }
+    }
+    bound
+}
+
+/// `two_cheapest_edges_bound` from lists holding at least the two nearest
+/// neighbours of every city, e.g. found with a KD-tree.
+pub fn nearest_neighbours_bound<D: DistanceProvider + ?Sized>(distances: &D, neighbours: &[Vec<u32>]) -> f64 {
+    let mut bound = 0.0;
+    for (i, city_neighbours) in neighbours.iter().enumerate() {
+        let mut cheapest = f64::INFINITY;
+        let mut second_cheapest = f64::INFINITY;
+        for &j in city_neighbours {
+            let distance = distances.distance(i, j as usize);
+            if distance < cheapest {
+                second_cheapest = cheapest;
+                cheapest = distance;
+            } else if distance < second_cheapest {
+                second_cheapest = distance;
+            }
+        }
+        if second_cheapest.is_finite() {
+            bound += (cheapest + second_cheapest) / 2.0;
+        }
+    }
+    bound
+}
+
+/// Lower bound for asymmetric instances: every city is left through an arc
+/// at least as cheap as its cheapest outgoing arc, and entered likewise.
+pub fn cheapest_arcs_bound<D: DistanceProvider + ?Sized>(distances: &D) -> f64 {
+    partial_cheapest_arcs_bound(distances, distances.dimension())
+}
+
+/// `cheapest_arcs_bound` from the rows of the first `cities` cities only, for
+/// `cities × n` distance lookups. Partial rows only bound the outgoing arcs.
+pub fn partial_cheapest_arcs_bound<D: DistanceProvider + ?Sized>(distances: &D, cities: usize) -> f64 {
+    let cities = cities.min(distances.dimension());
+    let mut outgoing = 0.0;
+    let mut incoming = vec![f64::INFINITY; distances.dimension()];
+    for i in 0..cities {
+        let mut cheapest = f64::INFINITY;
+        for (j, cheapest_incoming) in incoming.iter_mut().enumerate() {
+            if i == j {
+                continue;
+            }
+            let distance = distances.distance(i, j);
+            cheapest = cheapest.min(distance);
+            *cheapest_incoming = cheapest_incoming.min(distance);
+        }
+        if cheapest.is_finite() {
+            outgoing += cheapest;
+        }
+    }
+    if cities < distances.dimension() {
+        return outgoing;
+    }
+    let incoming: f64 = incoming.iter().filter(|distance| distance.is_finite()).sum();
+    outgoing.max(incoming)
+}
+
+/// Held-Karp lower bound: the best penalised minimum 1-tree found by
+/// `max_iterations` steps of subgradient optimisation. Usually within a
+/// percent or two of the optimum on symmetric instances. Expects symmetric costs.
+pub fn held_karp_bound<D: DistanceProvider + ?Sized>(distances: &D, max_iterations: usize) -> f64 {
+    if distances.dimension() < 3 {
+        return two_cheapest_edges_bound(distances);
+    }
+    one_tree::subgradient_ascent(distances, max_iterations).1
+}
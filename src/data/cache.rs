@@ -0,0 +1,492 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::data::candidates::CandidateLists;
+use crate::data::distances::{DistanceProvider, Distances, LazyDistances};
+use crate::data::error::InstanceError;
+use crate::data::instance::{Instance, LoadOptions};
+use crate::data::matrix::{DistanceMatrix, MatrixPrecision};
+use crate::data::node_ids::NodeIds;
+use crate::data::tsplib::{EdgeWeightType, RoundingMode, TsplibHeader};
+
+/// Bumped whenever the layout below changes; older caches are rebuilt.
+pub const CACHE_VERSION: u32 = 2;
+const MAGIC: &[u8; 4] = b"TSPC";
+/// Extension of cache files.
+pub const CACHE_EXTENSION: &str = "tspcache";
+
+// Layout: MAGIC, version (u32), payload length (u64), payload, CRC32 of the payload (u32).
+// All numbers are little endian, strings and sequences are prefixed with their length (u64).
+
+/// Files a cache is built from, as canonical paths so that the same file
+/// reached through different relative paths maps to the same cache.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheSources {
+    pub instance: PathBuf,
+    pub opt_tour: Option<PathBuf>,
+}
+
+impl CacheSources {
+    pub fn new(instance_path: &Path, opt_tour_path: Option<&Path>) -> CacheSources {
+        let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        CacheSources { instance: canonical(instance_path), opt_tour: opt_tour_path.map(canonical) }
+    }
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.usize(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: Option<T>, mut encode: impl FnMut(&mut Encoder, T)) {
+        self.bool(value.is_some());
+        if let Some(value) = value {
+            encode(self, value);
+        }
+    }
+
+    fn u32s(&mut self, values: &[u32]) {
+        self.usize(values.len());
+        for &value in values {
+            self.u32(value);
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    path: &'a Path,
+}
+
+impl<'a> Decoder<'a> {
+    fn invalid(&self, message: &str) -> InstanceError {
+        InstanceError::InvalidFile { path: self.path.to_path_buf(), message: message.to_string() }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], InstanceError> {
+        let end = self.position.checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| self.invalid("truncated cache"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], InstanceError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, InstanceError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, InstanceError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, InstanceError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn usize(&mut self) -> Result<usize, InstanceError> {
+        usize::try_from(self.u64()?).map_err(|_| self.invalid("length out of range"))
+    }
+
+    // Length prefix of a sequence whose elements take at least `element_size` bytes
+    fn len(&mut self, element_size: usize) -> Result<usize, InstanceError> {
+        let len = self.usize()?;
+        if len.saturating_mul(element_size) > self.bytes.len() - self.position {
+            return Err(self.invalid("truncated cache"));
+        }
+        Ok(len)
+    }
+
+    fn f64(&mut self) -> Result<f64, InstanceError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, InstanceError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn string(&mut self) -> Result<String, InstanceError> {
+        let len = self.len(1)?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.invalid("invalid string"))
+    }
+
+    fn option<T>(
+        &mut self,
+        mut decode: impl FnMut(&mut Decoder<'a>) -> Result<T, InstanceError>,
+    ) -> Result<Option<T>, InstanceError> {
+        if self.bool()? { Ok(Some(decode(self)?)) } else { Ok(None) }
+    }
+
+    fn u32s(&mut self) -> Result<Vec<u32>, InstanceError> {
+        let len = self.len(4)?;
+        (0..len).map(|_| self.u32()).collect()
+    }
+}
+
+fn encode_precision(precision: MatrixPrecision) -> u8 {
+    match precision {
+        MatrixPrecision::F64 => 0,
+        MatrixPrecision::F32 => 1,
+        MatrixPrecision::I32 => 2,
+    }
+}
+
+fn encode_rounding(rounding: RoundingMode) -> u8 {
+    match rounding {
+        RoundingMode::Exact => 0,
+        RoundingMode::Nint => 1,
+    }
+}
+
+fn encode_options(encoder: &mut Encoder, options: &LoadOptions) {
+    encoder.u8(encode_rounding(options.rounding));
+    encoder.u8(encode_precision(options.precision));
+    encoder.bool(options.triangular);
+    encoder.usize(options.lazy_threshold);
+}
+
+fn encode_distances(encoder: &mut Encoder, distances: &Distances) {
+    match distances {
+        Distances::Matrix(matrix) => {
+            encoder.u8(0);
+            encoder.usize(matrix.dimension());
+            encoder.bool(matrix.is_triangular());
+            encoder.u8(encode_precision(matrix.precision()));
+            // Stored cells only, in their native width
+            for i in 0..matrix.dimension() {
+                let columns = if matrix.is_triangular() { 0..i + 1 } else { 0..matrix.dimension() };
+                for j in columns {
+                    let value = matrix.get(i, j);
+                    match matrix.precision() {
+                        MatrixPrecision::F64 => encoder.f64(value),
+                        MatrixPrecision::F32 => encoder.bytes.extend_from_slice(&(value as f32).to_le_bytes()),
+                        MatrixPrecision::I32 => encoder.bytes.extend_from_slice(&(value as i32).to_le_bytes()),
+                    }
+                }
+            }
+        }
+        Distances::Lazy(lazy) => {
+            encoder.u8(1);
+            encoder.usize(lazy.points().len());
+            for &(x, y, z) in lazy.points() {
+                encoder.f64(x);
+                encoder.f64(y);
+                encoder.f64(z);
+            }
+            encoder.str(lazy.edge_weight_type().keyword());
+            encoder.u8(encode_rounding(lazy.rounding()));
+        }
+    }
+}
+
+fn encode_sources(encoder: &mut Encoder, sources: &CacheSources) {
+    encoder.str(&sources.instance.to_string_lossy());
+    encoder.option(sources.opt_tour.as_deref(), |encoder, path| encoder.str(&path.to_string_lossy()));
+}
+
+fn encode_instance(encoder: &mut Encoder, instance: &Instance, options: &LoadOptions) {
+    encode_options(encoder, options);
+    encoder.str(&instance.name);
+    let entries = instance.header.entries();
+    encoder.usize(entries.len());
+    for (keyword, value) in &entries {
+        encoder.str(keyword);
+        encoder.str(value);
+    }
+    match instance.node_ids() {
+        NodeIds::Sequential(dimension) => {
+            encoder.u8(0);
+            encoder.usize(*dimension);
+        }
+        NodeIds::Mapped { ids, .. } => {
+            encoder.u8(1);
+            encoder.usize(ids.len());
+            for &id in ids {
+                encoder.usize(id);
+            }
+        }
+    }
+    encoder.option(instance.optimal_solution.as_deref(), Encoder::u32s);
+    encode_distances(encoder, instance.distances());
+    encoder.option(instance.city_coords.as_deref(), |encoder, coords| {
+        encoder.usize(coords.len());
+        for &(x, y) in coords {
+            encoder.f64(x);
+            encoder.f64(y);
+        }
+    });
+    encoder.option(instance.city_z_coords.as_deref(), |encoder, z_coords| {
+        encoder.usize(z_coords.len());
+        for &z in z_coords {
+            encoder.f64(z);
+        }
+    });
+    encoder.str(instance.edge_weight_type.keyword());
+    encoder.bool(instance.is_symmetric);
+    encoder.u8(encode_rounding(instance.rounding));
+    encoder.option(instance.known_optimal_length, Encoder::f64);
+    encoder.option(instance.best_known_length, Encoder::f64);
+    encoder.option(instance.lower_bound.get().copied(), Encoder::f64);
+    encoder.option(instance.candidate_lists(), |encoder, candidates| {
+        encoder.usize(candidates.len());
+        for city_neighbours in candidates.as_slice() {
+            encoder.u32s(city_neighbours);
+        }
+    });
+}
+
+fn decode_precision(decoder: &mut Decoder) -> Result<MatrixPrecision, InstanceError> {
+    match decoder.u8()? {
+        0 => Ok(MatrixPrecision::F64),
+        1 => Ok(MatrixPrecision::F32),
+        2 => Ok(MatrixPrecision::I32),
+        _ => Err(decoder.invalid("invalid matrix precision")),
+    }
+}
+
+fn decode_rounding(decoder: &mut Decoder) -> Result<RoundingMode, InstanceError> {
+    match decoder.u8()? {
+        0 => Ok(RoundingMode::Exact),
+        1 => Ok(RoundingMode::Nint),
+        _ => Err(decoder.invalid("invalid rounding mode")),
+    }
+}
+
+fn decode_edge_weight_type(decoder: &mut Decoder) -> Result<EdgeWeightType, InstanceError> {
+    let keyword = decoder.string()?;
+    keyword.parse().map_err(|_| InstanceError::UnsupportedEdgeWeightType(keyword))
+}
+
+fn decode_options(decoder: &mut Decoder) -> Result<LoadOptions, InstanceError> {
+    Ok(LoadOptions {
+        rounding: decode_rounding(decoder)?,
+        precision: decode_precision(decoder)?,
+        triangular: decoder.bool()?,
+        lazy_threshold: decoder.usize()?,
+        cache_dir: None,
+    })
+}
+
+fn decode_distances(decoder: &mut Decoder) -> Result<Distances, InstanceError> {
+    match decoder.u8()? {
+        0 => {
+            let dimension = decoder.usize()?;
+            let triangular = decoder.bool()?;
+            let precision = decode_precision(decoder)?;
+            let cells = if triangular { dimension * (dimension + 1) / 2 } else { dimension * dimension };
+            let width = if precision == MatrixPrecision::F64 { 8 } else { 4 };
+            let bytes = decoder.take(cells.checked_mul(width).ok_or_else(|| decoder.invalid("truncated cache"))?)?;
+            let mut values = bytes.chunks_exact(width).map(|chunk| match precision {
+                MatrixPrecision::F64 => f64::from_le_bytes(chunk.try_into().unwrap()),
+                MatrixPrecision::F32 => f32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+                MatrixPrecision::I32 => i32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+            });
+            let mut matrix = DistanceMatrix::new(dimension, triangular, precision);
+            for i in 0..dimension {
+                let columns = if triangular { 0..i + 1 } else { 0..dimension };
+                for j in columns {
+                    matrix.set(i, j, values.next().unwrap());
+                }
+            }
+            Ok(Distances::Matrix(matrix))
+        }
+        1 => {
+            let len = decoder.len(24)?;
+            let points = (0..len)
+                .map(|_| Ok((decoder.f64()?, decoder.f64()?, decoder.f64()?)))
+                .collect::<Result<Vec<_>, InstanceError>>()?;
+            let edge_weight_type = decode_edge_weight_type(decoder)?;
+            let rounding = decode_rounding(decoder)?;
+            Ok(Distances::Lazy(LazyDistances::new(points, edge_weight_type, rounding)))
+        }
+        _ => Err(decoder.invalid("invalid distances")),
+    }
+}
+
+fn decode_sources(decoder: &mut Decoder) -> Result<CacheSources, InstanceError> {
+    let instance = PathBuf::from(decoder.string()?);
+    let opt_tour = decoder.option(|decoder| decoder.string().map(PathBuf::from))?;
+    Ok(CacheSources { instance, opt_tour })
+}
+
+fn decode_instance(decoder: &mut Decoder) -> Result<(Instance, LoadOptions), InstanceError> {
+    let options = decode_options(decoder)?;
+    let name = decoder.string()?;
+    let mut header = TsplibHeader::default();
+    let entries = decoder.len(16)?;
+    for _ in 0..entries {
+        let keyword = decoder.string()?;
+        let value = decoder.string()?;
+        header.set(&keyword, &value, 0)?;
+    }
+    let node_ids = match decoder.u8()? {
+        0 => NodeIds::sequential(decoder.usize()?),
+        1 => {
+            let len = decoder.len(8)?;
+            let ids = (0..len).map(|_| decoder.usize()).collect::<Result<Vec<_>, _>>()?;
+            NodeIds::from_sorted_ids(ids)?
+        }
+        _ => return Err(decoder.invalid("invalid node ids")),
+    };
+    let optimal_solution = decoder.option(Decoder::u32s)?;
+    let distances = decode_distances(decoder)?;
+    let city_coords = decoder.option(|decoder| {
+        let len = decoder.len(16)?;
+        (0..len).map(|_| Ok((decoder.f64()?, decoder.f64()?))).collect::<Result<Vec<_>, InstanceError>>()
+    })?;
+    let city_z_coords = decoder.option(|decoder| {
+        let len = decoder.len(8)?;
+        (0..len).map(|_| decoder.f64()).collect::<Result<Vec<_>, _>>()
+    })?;
+    let edge_weight_type = decode_edge_weight_type(decoder)?;
+    let is_symmetric = decoder.bool()?;
+    let rounding = decode_rounding(decoder)?;
+    let known_optimal_length = decoder.option(Decoder::f64)?;
+    let best_known_length = decoder.option(Decoder::f64)?;
+    let lower_bound = OnceLock::new();
+    if let Some(bound) = decoder.option(Decoder::f64)? {
+        let _ = lower_bound.set(bound);
+    }
+    let candidates = decoder.option(|decoder| {
+        let len = decoder.len(8)?;
+        (0..len).map(|_| decoder.u32s()).collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let dimension = distances.dimension();
+    if node_ids.len() != dimension
+        || optimal_solution.as_ref().is_some_and(|tour| tour.len() != dimension)
+        || candidates.as_ref().is_some_and(|candidates| candidates.len() != dimension)
+    {
+        return Err(decoder.invalid("inconsistent dimensions"));
+    }
+    let mut instance = Instance {
+        name,
+        header,
+        node_ids: Arc::new(node_ids),
+        optimal_solution,
+        distances: Arc::new(distances),
+        city_coords,
+        city_z_coords,
+        edge_weight_type,
+        is_symmetric,
+        rounding,
+        known_optimal_length,
+        best_known_length,
+        lower_bound,
+        candidates: None,
+    };
+    if let Some(candidates) = candidates {
+        instance.set_candidate_lists(CandidateLists::new(candidates));
+    }
+    Ok((instance, options))
+}
+
+/// Writes an instance, the files it was loaded from and the options it was
+/// loaded with to a binary cache file. The file is written next to its
+/// destination first and then moved in place, so readers never see a partial cache.
+pub fn write_cache<P: AsRef<Path>>(
+    path: P,
+    instance: &Instance,
+    sources: &CacheSources,
+    options: &LoadOptions,
+) -> Result<(), InstanceError> {
+    let path = path.as_ref();
+    let mut encoder = Encoder { bytes: Vec::new() };
+    encode_sources(&mut encoder, sources);
+    encode_instance(&mut encoder, instance, options);
+    let payload = encoder.bytes;
+
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+    let to_error = |source| InstanceError::Io { path: temporary_path.clone(), source };
+    let mut writer = BufWriter::new(File::create(&temporary_path).map_err(to_error)?);
+    writer.write_all(MAGIC).map_err(to_error)?;
+    writer.write_all(&CACHE_VERSION.to_le_bytes()).map_err(to_error)?;
+    writer.write_all(&(payload.len() as u64).to_le_bytes()).map_err(to_error)?;
+    writer.write_all(&payload).map_err(to_error)?;
+    writer.write_all(&crc32fast::hash(&payload).to_le_bytes()).map_err(to_error)?;
+    writer.flush().map_err(to_error)?;
+    drop(writer);
+    fs::rename(&temporary_path, path).map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })
+}
+
+/// Reads an instance from a binary cache file together with the files and the
+/// options it was loaded with, checking the format version and the checksum.
+pub fn read_cache<P: AsRef<Path>>(path: P) -> Result<(Instance, CacheSources, LoadOptions), InstanceError> {
+    let path = path.as_ref();
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })?;
+
+    let mut decoder = Decoder { bytes: &bytes, position: 0, path };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(decoder.invalid("not an instance cache"));
+    }
+    let version = decoder.u32()?;
+    if version != CACHE_VERSION {
+        return Err(decoder.invalid(&format!("cache version {} instead of {}", version, CACHE_VERSION)));
+    }
+    let payload_len = decoder.len(1)?;
+    let payload = decoder.take(payload_len)?;
+    if crc32fast::hash(payload) != decoder.u32()? {
+        return Err(decoder.invalid("checksum mismatch"));
+    }
+    let mut decoder = Decoder { bytes: payload, position: 0, path };
+    let sources = decode_sources(&mut decoder)?;
+    let (instance, options) = decode_instance(&mut decoder)?;
+    Ok((instance, sources, options))
+}
+
+/// Cache file used for the given sources inside `cache_dir`: the instance file
+/// name followed by a hash of the full source paths, so that same-named
+/// instances from different directories get different caches.
+pub fn cache_path(cache_dir: &Path, sources: &CacheSources) -> PathBuf {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(sources.instance.as_os_str().as_encoded_bytes());
+    if let Some(opt_tour) = &sources.opt_tour {
+        hasher.update(&[0]);
+        hasher.update(opt_tour.as_os_str().as_encoded_bytes());
+    }
+    let mut file_name = sources.instance.file_name().unwrap_or_default().to_owned();
+    file_name.push(format!(".{:08x}.{}", hasher.finalize(), CACHE_EXTENSION));
+    cache_dir.join(file_name)
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use crate::data::delaunay;
+use crate::data::instance::Instance;
+use crate::data::kdtree::KdTree;
+use crate::data::one_tree::{self, SymmetricDistances};
+use crate::data::tsplib::EdgeWeightType;
+
+/// Promising neighbours of every city (0-based indices), closest first.
+/// Moves restricted to these edges keep local search near-linear on big instances.
+#[derive(Clone, Debug)]
+pub struct CandidateLists {
+    neighbours: Vec<Vec<u32>>,
+}
+
+impl CandidateLists {
+    pub fn new(neighbours: Vec<Vec<u32>>) -> CandidateLists {
+        CandidateLists { neighbours }
+    }
+
+    /// The `k` nearest neighbours of every city.
+    pub fn nearest_neighbours(instance: &Instance, k: usize) -> CandidateLists {
+        let k = k.min(instance.dimension().saturating_sub(1));
+        let neighbours = match CandidateLists::planar_coords(instance) {
+            Some(coords) => {
+                let tree = KdTree::new(coords);
+                (0..coords.len())
+                    .map(|city| tree.nearest(coords[city], k, |other| other as usize != city))
+                    .collect()
+            }
+            None => (0..instance.dimension())
+                .map(|city| CandidateLists::scan_nearest(instance, city, k))
+                .collect(),
+        };
+        CandidateLists { neighbours }
+    }
+
+    /// The `k` nearest neighbours of every city in each of the four quadrants
+    /// around it, which avoids candidate sets made only of one dense cluster.
+    /// Instances without planar coordinates get plain nearest neighbours instead.
+    pub fn quadrant_neighbours(instance: &Instance, k: usize) -> CandidateLists {
+        let coords = match CandidateLists::planar_coords(instance) {
+            Some(coords) => coords,
+            None => return CandidateLists::nearest_neighbours(instance, 4 * k),
+        };
+        let tree = KdTree::new(coords);
+        let neighbours = (0..coords.len())
+            .map(|city| {
+                let (x, y) = coords[city];
+                let mut city_neighbours: Vec<u32> = Vec::new();
+                for quadrant in 0..4 {
+                    let in_quadrant = |other: u32| {
+                        let (other_x, other_y) = coords[other as usize];
+                        other as usize != city
+                            && match quadrant {
+                                0 => other_x >= x && other_y > y,
+                                1 => other_x < x && other_y >= y,
+                                2 => other_x <= x && other_y < y,
+                                _ => other_x > x && other_y <= y,
+                            }
+                    };
+                    city_neighbours.extend(tree.nearest((x, y), k, in_quadrant));
+                }
+                city_neighbours.sort_by(|&a, &b| {
+                    instance.distance(city, a as usize).total_cmp(&instance.distance(city, b as usize))
+                });
+                city_neighbours
+            })
+            .collect();
+        CandidateLists { neighbours }
+    }
+
+    /// Neighbours of every city in the Delaunay triangulation of its
+    /// coordinates, closest first. `None` for instances without planar coordinates.
+    pub fn delaunay(instance: &Instance) -> Option<CandidateLists> {
+        if instance.edge_weight_type.is_3d() {
+            return None;
+        }
+        let coords = instance.city_coords.as_ref()?;
+        let mut neighbours = delaunay::delaunay_neighbours(coords);
+        for (city, city_neighbours) in neighbours.iter_mut().enumerate() {
+            city_neighbours.sort_by(|&a, &b| {
+                instance.distance(city, a as usize).total_cmp(&instance.distance(city, b as usize))
+            });
+        }
+        Some(CandidateLists { neighbours })
+    }
+
+    /// The `k` alpha-nearest neighbours of every city, from the minimum 1-tree
+    /// under node penalties tuned by `ascent_iterations` subgradient steps
+    /// (none for plain 1-tree costs). Works on any instance, geometric or not;
+    /// asymmetric ones use the cheaper direction of every edge.
+    pub fn alpha_nearness(instance: &Instance, k: usize, ascent_iterations: usize) -> CandidateLists {
+        if instance.dimension() < 3 {
+            return CandidateLists::nearest_neighbours(instance, k);
+        }
+        let distances = SymmetricDistances(instance.distances());
+        let penalties = if ascent_iterations > 0 {
+            one_tree::subgradient_ascent(&distances, ascent_iterations).0
+        } else {
+            vec![0.0; instance.dimension()]
+        };
+        CandidateLists { neighbours: one_tree::alpha_nearest(&distances, &penalties, k) }
+    }
+
+    // The tree ranks points by Euclidean distance, which only orders neighbours
+    // correctly for the planar Euclidean-like edge weight types
+    pub(crate) fn planar_coords(instance: &Instance) -> Option<&[(f64, f64)]> {
+        match instance.edge_weight_type {
+            EdgeWeightType::Euc2d | EdgeWeightType::Ceil2d | EdgeWeightType::Att => instance.city_coords.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn scan_nearest(instance: &Instance, city: usize, k: usize) -> Vec<u32> {
+        let mut others: Vec<u32> = (0..instance.dimension() as u32)
+            .filter(|&other| other as usize != city)
+            .collect();
+        let by_distance = |a: &u32, b: &u32| {
+            instance.distance(city, *a as usize).total_cmp(&instance.distance(city, *b as usize))
+        };
+        if k < others.len() {
+            others.select_nth_unstable_by(k, by_distance);
+            others.truncate(k);
+        }
+        others.sort_by(by_distance);
+        others
+    }
+
+    pub fn neighbours(&self, city: usize) -> &[u32] {
+        &self.neighbours[city]
+    }
+
+    pub fn as_slice(&self) -> &[Vec<u32>] {
+        &self.neighbours
+    }
+
+    pub fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbours.is_empty()
+    }
+
+    /// Every candidate edge once, as `(smaller, larger)` city pairs.
+    pub fn edges(&self) -> Vec<(u32, u32)> {
+        let mut edges = HashSet::new();
+        for (city, city_neighbours) in self.neighbours.iter().enumerate() {
+            for &neighbour in city_neighbours {
+                let city = city as u32;
+                edges.insert((city.min(neighbour), city.max(neighbour)));
+            }
+        }
+        let mut edges: Vec<(u32, u32)> = edges.into_iter().collect();
+        edges.sort_unstable();
+        edges
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+
+use crate::data::error::InstanceError;
+
+/// Extensions of the compressed files the loaders read transparently.
+pub const COMPRESSED_EXTENSIONS: [&str; 2] = ["gz", "bz2"];
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+/// Opens a file for reading, decompressing gzip and bzip2 files on the fly.
+/// The format is recognised from the magic bytes, so the extension does not matter.
+pub fn open_reader(path: &Path) -> Result<Box<dyn BufRead>, InstanceError> {
+    let to_error = |source| InstanceError::Io { path: path.to_path_buf(), source };
+    let mut reader = BufReader::new(File::open(path).map_err(to_error)?);
+    let start = reader.fill_buf().map_err(to_error)?;
+    if start.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if start.starts_with(BZIP2_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiBzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// The path without its compression extension, e.g. `a280.tsp` for `a280.tsp.gz`.
+pub fn strip_compression_extension(path: &Path) -> PathBuf {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if COMPRESSED_EXTENSIONS.contains(&extension) => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// The path itself if it exists, otherwise its first existing compressed variant.
+pub fn existing_variant(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    COMPRESSED_EXTENSIONS.iter()
+        .map(|extension| {
+            let mut compressed = path.as_os_str().to_owned();
+            compressed.push(".");
+            compressed.push(extension);
+            PathBuf::from(compressed)
+        })
+        .find(|compressed| compressed.is_file())
+}
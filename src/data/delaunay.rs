@@ -0,0 +1,324 @@
+use std::collections::{HashMap, HashSet};
+
+// Relative tolerance of the in-circumcircle test, so cocircular points
+// (e.g. on grids) are treated consistently
+const EPSILON: f64 = 1e-9;
+
+// Vertex at infinity closing every hull edge into a "ghost" triangle, which
+// spares the finite super triangle whose corners can cut off hull triangles
+const GHOST: usize = usize::MAX;
+
+// Counter-clockwise triangle, ghost ones having `GHOST` as their last vertex
+struct Triangle {
+    vertices: [usize; 3],
+    center: (f64, f64),
+    squared_radius: f64,
+}
+
+fn orientation(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+impl Triangle {
+    fn new(vertices: [usize; 3], points: &[(f64, f64)]) -> Triangle {
+        if vertices[2] == GHOST {
+            return Triangle { vertices, center: (f64::NAN, f64::NAN), squared_radius: f64::INFINITY };
+        }
+        // Relative to the first vertex, so far away point sets keep their precision
+        let (ax, ay) = points[vertices[0]];
+        let (bx, by) = (points[vertices[1]].0 - ax, points[vertices[1]].1 - ay);
+        let (cx, cy) = (points[vertices[2]].0 - ax, points[vertices[2]].1 - ay);
+        let d = 2.0 * (bx * cy - by * cx);
+        if d == 0.0 {
+            // Collinear vertices: the circumcircle is the whole plane
+            return Triangle { vertices, center: (ax, ay), squared_radius: f64::INFINITY };
+        }
+        let b2 = bx * bx + by * by;
+        let c2 = cx * cx + cy * cy;
+        let ux = (cy * b2 - by * c2) / d;
+        let uy = (bx * c2 - cx * b2) / d;
+        Triangle { vertices, center: (ax + ux, ay + uy), squared_radius: ux * ux + uy * uy }
+    }
+
+    // Counter-clockwise triangle with any ghost vertex rotated to the end
+    fn oriented(a: usize, b: usize, c: usize, points: &[(f64, f64)]) -> Triangle {
+        let vertices = match (a, b, c) {
+            (GHOST, b, c) => [b, c, GHOST],
+            (a, GHOST, c) => [c, a, GHOST],
+            _ => [a, b, c],
+        };
+        Triangle::new(vertices, points)
+    }
+
+    fn is_ghost(&self) -> bool {
+        self.vertices[2] == GHOST
+    }
+
+    // Whether the point falls in the circumcircle, cocircular points included.
+    // The circumcircle of a ghost triangle is the open half-plane beyond its
+    // hull edge, plus the edge itself.
+    fn encloses(&self, point: (f64, f64), points: &[(f64, f64)]) -> bool {
+        if self.is_ghost() {
+            let (a, b) = (points[self.vertices[0]], points[self.vertices[1]]);
+            let side = orientation(a, b, point);
+            let squared_length = (b.0 - a.0) * (b.0 - a.0) + (b.1 - a.1) * (b.1 - a.1);
+            if side.abs() > EPSILON * squared_length {
+                return side > 0.0;
+            }
+            // On the edge line: inside only between both ends
+            let dot = (point.0 - a.0) * (b.0 - a.0) + (point.1 - a.1) * (b.1 - a.1);
+            return dot > 0.0 && dot < squared_length;
+        }
+        // Incircle determinant of the lifted points, tolerance relative to its terms
+        let [a, b, c] = self.vertices.map(|vertex| (points[vertex].0 - point.0, points[vertex].1 - point.1));
+        let lift = |(x, y): (f64, f64)| x * x + y * y;
+        let terms = [
+            (lift(a), b.0 * c.1, c.0 * b.1),
+            (lift(b), c.0 * a.1, a.0 * c.1),
+            (lift(c), a.0 * b.1, b.0 * a.1),
+        ];
+        let determinant: f64 = terms.iter().map(|&(lift, left, right)| lift * (left - right)).sum();
+        let magnitude: f64 = terms.iter().map(|&(lift, left, right)| lift * (left.abs() + right.abs())).sum();
+        determinant >= -EPSILON * magnitude
+    }
+
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.vertices;
+        [(a, b), (b, c), (c, a)]
+    }
+}
+
+/// Delaunay triangulation of a planar point set (Bowyer-Watson with an
+/// x-sorted sweep, as described by Paul Bourke, and a vertex at infinity in
+/// place of the super triangle). Returns triangles as point index triples.
+/// Duplicated points are left out of the triangulation.
+pub fn triangulate(points: &[(f64, f64)]) -> Vec<[u32; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| points[a].0.total_cmp(&points[b].0).then(points[a].1.total_cmp(&points[b].1)));
+    order.dedup_by(|a, b| points[*a] == points[*b]);
+
+    // Start from the first two points and the first one off their line
+    let (first, second) = match order[..] {
+        [first, second, ..] => (first, second),
+        _ => return Vec::new(),
+    };
+    let third = match order[2..].iter()
+        .position(|&index| orientation(points[first], points[second], points[index]) != 0.0)
+    {
+        Some(position) => order.remove(position + 2),
+        None => return Vec::new(),
+    };
+    let (a, b, c) = if orientation(points[first], points[second], points[third]) > 0.0 {
+        (first, second, third)
+    } else {
+        (second, first, third)
+    };
+    let mut open = vec![
+        Triangle::new([a, b, c], points),
+        Triangle::oriented(b, a, GHOST, points),
+        Triangle::oriented(c, b, GHOST, points),
+        Triangle::oriented(a, c, GHOST, points),
+    ];
+    let mut closed: Vec<[usize; 3]> = Vec::new();
+
+    for &point_index in &order[2..] {
+        let point = points[point_index];
+        // Directed cavity edges; an edge shared by two removed triangles cancels out
+        let mut boundary: HashSet<(usize, usize)> = HashSet::new();
+        let mut i = 0;
+        while i < open.len() {
+            let triangle = &open[i];
+            let dx = point.0 - triangle.center.0;
+            // Points come sorted by x, so no later point can fall in this circumcircle
+            if !triangle.is_ghost()
+                && dx > 0.0
+                && dx * dx > triangle.squared_radius * (1.0 + EPSILON)
+            {
+                closed.push(triangle.vertices);
+                open.swap_remove(i);
+                continue;
+            }
+            if triangle.encloses(point, points) {
+                for (from, to) in triangle.edges() {
+                    if !boundary.remove(&(to, from)) {
+                        boundary.insert((from, to));
+                    }
+                }
+                open.swap_remove(i);
+                continue;
+            }
+            i += 1;
+        }
+        for (from, to) in boundary {
+            open.push(Triangle::oriented(from, to, point_index, points));
+        }
+    }
+
+    closed.extend(open.iter().map(|triangle| triangle.vertices));
+    closed.into_iter()
+        .filter(|triangle| triangle[2] != GHOST)
+        .map(|[a, b, c]| [a as u32, b as u32, c as u32])
+        .collect()
+}
+
+/// Neighbours of every point in the Delaunay graph, sorted by index.
+/// Duplicated points share the neighbours of the first copy and are linked to it.
+pub fn delaunay_neighbours(points: &[(f64, f64)]) -> Vec<Vec<u32>> {
+    let n = points.len();
+    let mut neighbours: Vec<Vec<u32>> = vec![Vec::new(); n];
+    let link = |neighbours: &mut Vec<Vec<u32>>, a: u32, b: u32| {
+        if a != b {
+            neighbours[a as usize].push(b);
+            neighbours[b as usize].push(a);
+        }
+    };
+
+    let triangles = triangulate(points);
+    if triangles.is_empty() {
+        // Fewer than three distinct points or all of them collinear: chain them in order
+        let mut order: Vec<u32> = (0..n as u32).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (points[a as usize], points[b as usize]);
+            a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+        for pair in order.windows(2) {
+            link(&mut neighbours, pair[0], pair[1]);
+        }
+    }
+    for [a, b, c] in triangles {
+        link(&mut neighbours, a, b);
+        link(&mut neighbours, b, c);
+        link(&mut neighbours, c, a);
+    }
+
+    let mut first_copy: HashMap<(u64, u64), u32> = HashMap::new();
+    for (index, &(x, y)) in points.iter().enumerate() {
+        let original = *first_copy.entry((x.to_bits(), y.to_bits())).or_insert(index as u32);
+        if original as usize != index && neighbours[index].is_empty() {
+            neighbours[index] = neighbours[original as usize].clone();
+            link(&mut neighbours, original, index as u32);
+        }
+    }
+
+    for city_neighbours in neighbours.iter_mut() {
+        city_neighbours.sort_unstable();
+        city_neighbours.dedup();
+    }
+    neighbours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    }
+
+    // Number of vertices of the convex hull (monotone chain, collinear points left out)
+    fn hull_size(points: &[(f64, f64)]) -> usize {
+        let mut sorted = points.to_vec();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        let mut hull: Vec<(f64, f64)> = Vec::new();
+        for pass in 0..2 {
+            let start = hull.len();
+            for &point in &sorted {
+                while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                    hull.pop();
+                }
+                hull.push(point);
+            }
+            hull.pop();
+            if pass == 0 {
+                sorted.reverse();
+            }
+        }
+        hull.len()
+    }
+
+    fn assert_empty_circumcircles(points: &[(f64, f64)], triangles: &[[u32; 3]]) {
+        for triangle in triangles {
+            let circle = Triangle::new(triangle.map(|vertex| vertex as usize), points);
+            for (index, &(x, y)) in points.iter().enumerate() {
+                if triangle.contains(&(index as u32)) {
+                    continue;
+                }
+                let squared_distance = (x - circle.center.0).powi(2) + (y - circle.center.1).powi(2);
+                assert!(
+                    squared_distance >= circle.squared_radius * (1.0 - 1e-9),
+                    "point {} lies inside the circumcircle of {:?}", index, triangle
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn square_with_centre() {
+        let points = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.5, 0.5)];
+        assert_eq!(triangulate(&points).len(), 4);
+        let neighbours = delaunay_neighbours(&points);
+        assert_eq!(neighbours[4], vec![0, 1, 2, 3]);
+        assert_eq!(neighbours[0], vec![1, 3, 4]);
+        assert_eq!(neighbours[1], vec![0, 2, 4]);
+        assert_eq!(neighbours[2], vec![1, 3, 4]);
+        assert_eq!(neighbours[3], vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn grid() {
+        let points: Vec<(f64, f64)> = (0..9).map(|i| ((i % 3) as f64, (i / 3) as f64)).collect();
+        let triangles = triangulate(&points);
+        // 2n - 2 - h with all 8 boundary points on the hull
+        assert_eq!(triangles.len(), 8);
+        assert_empty_circumcircles(&points, &triangles);
+        let neighbours = delaunay_neighbours(&points);
+        for i in 0..9u32 {
+            if i % 3 < 2 {
+                assert!(neighbours[i as usize].contains(&(i + 1)), "missing grid edge {}-{}", i, i + 1);
+            }
+            if i < 6 {
+                assert!(neighbours[i as usize].contains(&(i + 3)), "missing grid edge {}-{}", i, i + 3);
+            }
+        }
+        assert_eq!(neighbours[4].len(), neighbours.iter().map(Vec::len).max().unwrap());
+    }
+
+    #[test]
+    fn collinear_points_form_a_chain() {
+        let points = [(2.0, 2.0), (0.0, 0.0), (3.0, 3.0), (1.0, 1.0)];
+        assert!(triangulate(&points).is_empty());
+        let neighbours = delaunay_neighbours(&points);
+        assert_eq!(neighbours, vec![vec![2, 3], vec![3], vec![0], vec![0, 1]]);
+    }
+
+    #[test]
+    fn duplicated_points() {
+        let points = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)];
+        let triangles = triangulate(&points);
+        assert_eq!(triangles.len(), 2);
+        assert!(triangles.iter().all(|triangle| !triangle.contains(&4)));
+        let neighbours = delaunay_neighbours(&points);
+        assert!(neighbours[1].contains(&4));
+        assert!(neighbours[4].contains(&1));
+        assert!(neighbours[4].contains(&0) && neighbours[4].contains(&3));
+        assert!(neighbours.iter().all(|city_neighbours| !city_neighbours.is_empty()));
+    }
+
+    #[test]
+    fn random_points_triangle_count() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let points: Vec<(f64, f64)> = (0..500)
+            .map(|_| (rng.gen_range(0.0..1000.0), rng.gen_range(0.0..1000.0)))
+            .collect();
+        let triangles = triangulate(&points);
+        assert_eq!(triangles.len(), 2 * points.len() - 2 - hull_size(&points));
+        assert_empty_circumcircles(&points, &triangles);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::data::matrix::DistanceMatrix;
+use crate::data::tsplib::{EdgeWeightType, RoundingMode};
+
+/// Source of the cost of going from one city to another (0-based indices).
+pub trait DistanceProvider {
+    fn dimension(&self) -> usize;
+    fn distance(&self, i: usize, j: usize) -> f64;
+}
+
+impl DistanceProvider for DistanceMatrix {
+    fn dimension(&self) -> usize {
+        DistanceMatrix::dimension(self)
+    }
+
+    #[inline]
+    fn distance(&self, i: usize, j: usize) -> f64 {
+        self.get(i, j)
+    }
+}
+
+/// Computes distances from coordinates on every lookup, using O(n) memory
+/// instead of the O(n²) of a full matrix.
+#[derive(Clone, Debug)]
+pub struct LazyDistances {
+    points: Vec<(f64, f64, f64)>,
+    edge_weight_type: EdgeWeightType,
+    rounding: RoundingMode,
+    // Per city (neighbour, distance) pairs sorted by neighbour, binary searched
+    // before computing anything
+    neighbour_cache: Option<Vec<Vec<(u32, f64)>>>,
+}
+
+impl LazyDistances {
+    pub fn new(points: Vec<(f64, f64, f64)>, edge_weight_type: EdgeWeightType, rounding: RoundingMode) -> LazyDistances {
+        LazyDistances { points, edge_weight_type, rounding, neighbour_cache: None }
+    }
+
+    /// Precomputes the distances from every city to the given neighbours, which
+    /// are the ones local searches ask for over and over again. Cheap edge
+    /// weight types are left uncached, as the lookup would cost more than it saves.
+    pub fn cache_neighbours(&mut self, neighbours: &[Vec<u32>]) {
+        if !self.edge_weight_type.is_costly() {
+            return;
+        }
+        let cache = neighbours.iter()
+            .enumerate()
+            .map(|(i, city_neighbours)| {
+                let mut row: Vec<(u32, f64)> = city_neighbours.iter()
+                    .map(|&j| (j, self.compute(i, j as usize)))
+                    .collect();
+                row.sort_unstable_by_key(|&(neighbour, _)| neighbour);
+                row.dedup_by_key(|&mut (neighbour, _)| neighbour);
+                row
+            })
+            .collect();
+        self.neighbour_cache = Some(cache);
+    }
+
+    pub fn points(&self) -> &[(f64, f64, f64)] {
+        &self.points
+    }
+
+    pub fn edge_weight_type(&self) -> EdgeWeightType {
+        self.edge_weight_type
+    }
+
+    pub fn rounding(&self) -> RoundingMode {
+        self.rounding
+    }
+
+    #[inline]
+    fn compute(&self, i: usize, j: usize) -> f64 {
+        self.rounding.apply(self.edge_weight_type.distance(self.points[i], self.points[j]))
+    }
+}
+
+impl DistanceProvider for LazyDistances {
+    fn dimension(&self) -> usize {
+        self.points.len()
+    }
+
+    fn distance(&self, i: usize, j: usize) -> f64 {
+        if i == j {
+            return 0.0;
+        }
+        if let Some(cache) = &self.neighbour_cache {
+            if let Ok(position) = cache[i].binary_search_by_key(&(j as u32), |&(neighbour, _)| neighbour) {
+                return cache[i][position].1;
+            }
+        }
+        self.compute(i, j)
+    }
+}
+
+/// Distance backend held by an `Instance`.
+#[derive(Clone, Debug)]
+pub enum Distances {
+    Matrix(DistanceMatrix),
+    Lazy(LazyDistances),
+}
+
+impl DistanceProvider for Distances {
+    fn dimension(&self) -> usize {
+        match self {
+            Distances::Matrix(matrix) => DistanceProvider::dimension(matrix),
+            Distances::Lazy(lazy) => lazy.dimension(),
+        }
+    }
+
+    #[inline]
+    fn distance(&self, i: usize, j: usize) -> f64 {
+        match self {
+            Distances::Matrix(matrix) => matrix.get(i, j),
+            Distances::Lazy(lazy) => lazy.distance(i, j),
+        }
+    }
+}
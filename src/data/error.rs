@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum InstanceError {
+    Io { path: PathBuf, source: io::Error },
+    InvalidFile { path: PathBuf, message: String },
+    MissingColumn(String),
+    UnsupportedEdgeWeightType(String),
+    UnsupportedEdgeWeightFormat(String),
+    UnsupportedProblemType(String),
+    MissingKeyword(String),
+    InvalidHeader { line: usize, keyword: String, value: String },
+    InvalidCoordinate { line: usize, content: String },
+    InvalidEdgeWeight { line: usize, content: String },
+    InvalidTourEntry { line: usize, content: String },
+    MissingNode(usize),
+    DuplicateNode(usize),
+    Unreachable { from: usize, to: usize },
+    IncompatibleInstances(String),
+    DimensionMismatch { expected: usize, found: usize },
+    EdgeWeightCount { expected: usize, found: usize },
+    OptimumMismatch { expected: f64, found: f64 },
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceError::Io { path, source } =>
+                write!(f, "cannot read {}: {}", path.display(), source),
+            InstanceError::InvalidFile { path, message } =>
+                write!(f, "cannot parse {}: {}", path.display(), message),
+            InstanceError::MissingColumn(columns) =>
+                write!(f, "missing {} columns", columns),
+            InstanceError::UnsupportedEdgeWeightType(value) =>
+                write!(f, "unsupported EDGE_WEIGHT_TYPE {:?}", value),
+            InstanceError::UnsupportedEdgeWeightFormat(value) =>
+                write!(f, "unsupported EDGE_WEIGHT_FORMAT {:?}", value),
+            InstanceError::UnsupportedProblemType(value) =>
+                write!(f, "unsupported TYPE {:?}, expected TSP or ATSP", value),
+            InstanceError::MissingKeyword(keyword) =>
+                write!(f, "missing {} in the specification part", keyword),
+            InstanceError::InvalidHeader { line, keyword, value } =>
+                write!(f, "line {}: invalid value {:?} for {}", line, value, keyword),
+            InstanceError::InvalidCoordinate { line, content } =>
+                write!(f, "line {}: invalid node coordinate entry {:?}", line, content),
+            InstanceError::InvalidEdgeWeight { line, content } =>
+                write!(f, "line {}: invalid edge weight entry {:?}", line, content),
+            InstanceError::InvalidTourEntry { line, content } =>
+                write!(f, "line {}: invalid tour entry {:?}", line, content),
+            InstanceError::MissingNode(id) =>
+                write!(f, "node {} is missing from the node section", id),
+            InstanceError::DuplicateNode(id) =>
+                write!(f, "node {} is given more than once", id),
+            InstanceError::Unreachable { from, to } =>
+                write!(f, "node {} cannot be reached from node {}", to, from),
+            InstanceError::IncompatibleInstances(reason) =>
+                write!(f, "cannot combine instances: {}", reason),
+            InstanceError::DimensionMismatch { expected, found } =>
+                write!(f, "expected {} nodes but found {}", expected, found),
+            InstanceError::EdgeWeightCount { expected, found } =>
+                write!(f, "expected {} edge weights but found {}", expected, found),
+            InstanceError::OptimumMismatch { expected, found } =>
+                write!(f, "optimal tour has length {} but the published optimum is {}", found, expected),
+        }
+    }
+}
+
+impl Error for InstanceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InstanceError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::data::instance::{Instance, LoadOptions};
+use crate::data::tsplib::EdgeWeightType;
+
+/// Side of the square all generated points lie in, as in the DIMACS TSP challenge.
+pub const SQUARE_SIDE: f64 = 1_000_000.0;
+
+// Coordinates are rounded to integers so saved `.tsp` files reload to the same instance
+fn build(name: String, points: Vec<(f64, f64)>) -> Instance {
+    let points = points.into_iter().map(|(x, y)| (x.round(), y.round())).collect();
+    Instance::from_coords(&name, points, EdgeWeightType::Euc2d, &LoadOptions::default())
+        .expect("EUC_2D distances come from coordinates")
+}
+
+/// `dimension` points drawn uniformly in the square (DIMACS `portgen`).
+pub fn uniform(dimension: usize, seed: u64) -> Instance {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let points = (0..dimension)
+        .map(|_| (rng.gen_range(0.0..SQUARE_SIDE), rng.gen_range(0.0..SQUARE_SIDE)))
+        .collect();
+    build(format!("E{}.{}", dimension, seed), points)
+}
+
+/// Clustered points (DIMACS `portcgen`): `dimension / 10` uniform centres,
+/// every point normally distributed around a random one of them with a
+/// standard deviation of `SQUARE_SIDE / sqrt(dimension)`.
+pub fn clustered(dimension: usize, seed: u64) -> Instance {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let centres: Vec<(f64, f64)> = (0..(dimension / 10).max(1))
+        .map(|_| (rng.gen_range(0.0..SQUARE_SIDE), rng.gen_range(0.0..SQUARE_SIDE)))
+        .collect();
+    let deviation = SQUARE_SIDE / (dimension.max(1) as f64).sqrt();
+    let points = (0..dimension)
+        .map(|_| {
+            let (x, y) = centres[rng.gen_range(0..centres.len())];
+            (x + deviation * standard_normal(&mut rng), y + deviation * standard_normal(&mut rng))
+        })
+        .collect();
+    build(format!("C{}.{}", dimension, seed), points)
+}
+
+/// Points on a square grid filling the square row by row, each moved by up
+/// to `perturbation` times the grid spacing in both directions.
+pub fn perturbed_grid(dimension: usize, perturbation: f64, seed: u64) -> Instance {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let side = (dimension as f64).sqrt().ceil().max(1.0) as usize;
+    let spacing = SQUARE_SIDE / side as f64;
+    let points = (0..dimension)
+        .map(|i| {
+            let (column, row) = ((i % side) as f64, (i / side) as f64);
+            let mut offset = || perturbation * spacing * rng.gen_range(-1.0..=1.0);
+            ((column + 0.5) * spacing + offset(), (row + 0.5) * spacing + offset())
+        })
+        .collect();
+    build(format!("G{}.{}", dimension, seed), points)
+}
+
+// Box-Muller transform
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+use serde_json::Value;
+
+use crate::data::error::InstanceError;
+use crate::data::instance::{Instance, LoadOptions};
+use crate::data::node_ids::NodeIds;
+use crate::data::tsplib::EdgeWeightType;
+
+const ID_COLUMNS: [&str; 1] = ["id"];
+const LATITUDE_COLUMNS: [&str; 2] = ["lat", "latitude"];
+const LONGITUDE_COLUMNS: [&str; 4] = ["lon", "lng", "long", "longitude"];
+const X_COLUMNS: [&str; 1] = ["x"];
+const Y_COLUMNS: [&str; 1] = ["y"];
+
+fn instance_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Sorts the points by id like the TSPLIB loader and attaches the id mapping
+fn build(
+    path: &Path,
+    mut points: Vec<(usize, (f64, f64))>,
+    edge_weight_type: EdgeWeightType,
+    options: &LoadOptions,
+) -> Result<Instance, InstanceError> {
+    points.sort_by_key(|&(id, _)| id);
+    let node_ids = NodeIds::from_sorted_ids(points.iter().map(|&(id, _)| id).collect())?;
+    let coords = points.into_iter().map(|(_, point)| point).collect();
+    let mut instance = Instance::from_coords(&instance_name(path), coords, edge_weight_type, options)?;
+    instance.set_node_ids(node_ids);
+    Ok(instance)
+}
+
+/// Reads a CSV point set with a header row. `lat`/`lon` columns (decimal
+/// degrees) give a `Haversine` instance, `x`/`y` columns a `EUC_2D` one.
+/// An optional integer `id` column sets the node ids, which otherwise follow the rows from 1.
+pub fn from_csv<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Instance, InstanceError> {
+    let path = path.as_ref();
+    let invalid_file = |message: String| InstanceError::InvalidFile { path: path.to_path_buf(), message };
+    let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_reader(Instance::open(path)?);
+    let headers = reader.headers().map_err(|error| invalid_file(error.to_string()))?.clone();
+    let column = |names: &[&str]| headers.iter().position(|header| names.contains(&header.to_lowercase().as_str()));
+
+    let id_column = column(&ID_COLUMNS);
+    let (edge_weight_type, first_column, second_column) =
+        match (column(&LATITUDE_COLUMNS), column(&LONGITUDE_COLUMNS), column(&X_COLUMNS), column(&Y_COLUMNS)) {
+            (Some(latitude), Some(longitude), _, _) => (EdgeWeightType::Haversine, latitude, longitude),
+            (_, _, Some(x), Some(y)) => (EdgeWeightType::Euc2d, x, y),
+            _ => return Err(InstanceError::MissingColumn("lat/lon or x/y".to_string())),
+        };
+
+    let mut points = Vec::new();
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record).map_err(|error| invalid_file(error.to_string()))? {
+        let line = record.position().map_or(0, |position| position.line() as usize);
+        let invalid_coordinate = || InstanceError::InvalidCoordinate {
+            line,
+            content: record.iter().collect::<Vec<&str>>().join(","),
+        };
+        let field = |column: usize| record.get(column).ok_or_else(invalid_coordinate);
+        let id = match id_column {
+            Some(column) => field(column)?.parse::<usize>().map_err(|_| invalid_coordinate())?,
+            None => points.len() + 1,
+        };
+        let first = field(first_column)?.parse::<f64>().map_err(|_| invalid_coordinate())?;
+        let second = field(second_column)?.parse::<f64>().map_err(|_| invalid_coordinate())?;
+        points.push((id, (first, second)));
+    }
+    build(path, points, edge_weight_type, options)
+}
+
+/// Reads the points of a GeoJSON `FeatureCollection`, `Feature`, `Point` or
+/// `MultiPoint` as a `Haversine` instance. Node ids come from integer feature
+/// ids or `id` properties when every feature has one, otherwise from the order of the points.
+pub fn from_geojson<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Instance, InstanceError> {
+    let path = path.as_ref();
+    let invalid_file = |message: &str| InstanceError::InvalidFile { path: path.to_path_buf(), message: message.to_string() };
+    let document: Value = serde_json::from_reader(Instance::open(path)?)
+        .map_err(|error| invalid_file(&error.to_string()))?;
+
+    let features: Vec<&Value> = match document["type"].as_str() {
+        Some("FeatureCollection") => document["features"].as_array()
+            .ok_or_else(|| invalid_file("FeatureCollection without features"))?
+            .iter()
+            .collect(),
+        Some("Feature") => vec![&document],
+        Some("Point") | Some("MultiPoint") => vec![],
+        _ => return Err(invalid_file("expected a FeatureCollection, Feature, Point or MultiPoint")),
+    };
+
+    // (feature id, longitude/latitude pair) of every point
+    let mut positions: Vec<(Option<usize>, &Value)> = Vec::new();
+    if features.is_empty() {
+        collect_positions(&document, None, &mut positions).map_err(invalid_file)?;
+    }
+    for feature in features {
+        let id = feature["id"].as_u64()
+            .or_else(|| feature["properties"]["id"].as_u64())
+            .map(|id| id as usize);
+        collect_positions(&feature["geometry"], id, &mut positions).map_err(invalid_file)?;
+    }
+
+    let use_ids = positions.iter().all(|(id, _)| id.is_some());
+    let mut points = Vec::new();
+    for (index, (id, position)) in positions.into_iter().enumerate() {
+        // GeoJSON positions are [longitude, latitude]
+        let coordinate = |axis: usize| position[axis].as_f64().ok_or_else(|| invalid_file("invalid point coordinates"));
+        let (longitude, latitude) = (coordinate(0)?, coordinate(1)?);
+        let id = if use_ids { id.unwrap() } else { index + 1 };
+        points.push((id, (latitude, longitude)));
+    }
+    build(path, points, EdgeWeightType::Haversine, options)
+}
+
+fn collect_positions<'a>(
+    geometry: &'a Value,
+    id: Option<usize>,
+    positions: &mut Vec<(Option<usize>, &'a Value)>,
+) -> Result<(), &'static str> {
+    match geometry["type"].as_str() {
+        Some("Point") => positions.push((id, &geometry["coordinates"])),
+        Some("MultiPoint") => {
+            let points = geometry["coordinates"].as_array().ok_or("MultiPoint without coordinates")?;
+            // A feature id cannot name several nodes
+            let id = if points.len() == 1 { id } else { None };
+            positions.extend(points.iter().map(|point| (id, point)));
+        }
+        _ => return Err("only Point and MultiPoint geometries can be imported"),
+    }
+    Ok(())
+}
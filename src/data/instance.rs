@@ -1,133 +1,920 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use crate::data::utils;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use crate::data::bounds;
+use crate::data::cache;
+use crate::data::candidates::CandidateLists;
+use crate::data::compression;
+use crate::data::distances::{DistanceProvider, Distances, LazyDistances};
+use crate::data::error::InstanceError;
+use crate::data::matrix::{DistanceMatrix, MatrixPrecision};
+use crate::data::node_ids::NodeIds;
+use crate::data::one_tree::SymmetricDistances;
+use crate::data::tour;
+use crate::data::tsplib::{self, EdgeWeightFormat, EdgeWeightType, ProblemType, RoundingMode, TsplibHeader};
 
-const DATA_PATH: &str = "/Users/dawid/Private/School/Sem 1/Biologically Inspired Algorithms/data/tsp/SEL_tsp/";
+/// Environment variable pointing at the directory holding the `.tsp`/`.opt.tour` files.
+pub const DATA_PATH_ENV: &str = "TSP_DATA_PATH";
+const DEFAULT_DATA_PATH: &str = "data/tsp";
+/// Environment variable pointing at the directory parsed instances are cached in.
+pub const CACHE_PATH_ENV: &str = "TSP_CACHE_PATH";
+const DEFAULT_CACHE_PATH: &str = "data/cache";
+// Subgradient steps spent on the Held-Karp lower bound, each costing one
+// O(n²) minimum 1-tree. Lower bounds stay within a budget of distance lookups
+// on large instances, through fewer steps or fewer cities for the cheap bounds.
+const HELD_KARP_ITERATIONS: usize = 300;
+const LOWER_BOUND_DISTANCE_BUDGET: usize = 1_000_000_000;
+
+/// Directory used to resolve instance names, taken from `TSP_DATA_PATH` when set.
+pub fn data_root() -> PathBuf {
+    env::var_os(DATA_PATH_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_PATH))
+}
+
+/// Directory instances are cached in, taken from `TSP_CACHE_PATH` when set.
+pub fn cache_root() -> PathBuf {
+    env::var_os(CACHE_PATH_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CACHE_PATH))
+}
+
+/// Settings applied while turning instance files into an `Instance`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadOptions {
+    pub rounding: RoundingMode,
+    pub precision: MatrixPrecision,
+    // Store only one triangle of symmetric matrices
+    pub triangular: bool,
+    // Coordinate based instances above this dimension compute distances on the fly
+    pub lazy_threshold: usize,
+    // Parsed instances are cached here on first load and read back afterwards
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            rounding: RoundingMode::default(),
+            precision: MatrixPrecision::default(),
+            triangular: false,
+            lazy_threshold: 20_000,
+            cache_dir: None,
+        }
+    }
+}
+
+// Node coordinates as read, z being 0 for planar instances
+type Point = (f64, f64, f64);
+
+// Contents of a `.tsp` file needed to build an instance
+struct ParsedInstance {
+    header: TsplibHeader,
+    node_ids: NodeIds,
+    edge_weight_type: EdgeWeightType,
+    distances: Distances,
+    city_coords: Option<Vec<(f64, f64)>>,
+    city_z_coords: Option<Vec<f64>>,
+    is_symmetric: bool,
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Header,
+    NodeCoords,
+    EdgeWeights,
+    DisplayData,
+    Other,
+}
+
+/// Tour length solutions are scored against, from the most to the least reliable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreReference {
+    OptimalTour,
+    KnownOptimum,
+    BestKnown,
+    LowerBound,
+}
+
+impl ScoreReference {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreReference::OptimalTour => "Optimal Tour",
+            ScoreReference::KnownOptimum => "Known Optimum",
+            ScoreReference::BestKnown => "Best Known",
+            ScoreReference::LowerBound => "Lower Bound",
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Instance {
     pub name: String,
-    pub optimal_solution: Vec<i32>,
-    pub adjacency_matrix: Vec<Vec<f64>>,
-    pub city_coords: Vec<(f64, f64)>,
+    // Specification keywords as read from the instance file
+    pub header: TsplibHeader,
+    pub(crate) node_ids: Arc<NodeIds>,
+    // Tours are stored as 0-based city indices
+    pub optimal_solution: Option<Vec<u32>>,
+    pub(crate) distances: Arc<Distances>,
+    // Missing for EXPLICIT instances without display data
+    pub city_coords: Option<Vec<(f64, f64)>>,
+    // Only present for the *_3D edge weight types
+    pub city_z_coords: Option<Vec<f64>>,
+    pub edge_weight_type: EdgeWeightType,
+    // False for ATSP instances, where `distance(i, j)` is the cost of going from i to j
+    pub is_symmetric: bool,
+    pub rounding: RoundingMode,
+    // Published optimum for known TSPLIB instances
+    pub known_optimal_length: Option<f64>,
+    // Best tour length found so far, for instances that have not been solved
+    pub best_known_length: Option<f64>,
+    pub(crate) lower_bound: OnceLock<f64>,
+    pub(crate) candidates: Option<Arc<CandidateLists>>,
 }
 
 impl Instance {
-    pub fn new(name: &str) -> Instance {
+    pub fn new(name: &str) -> Result<Instance, InstanceError> {
+        Instance::from_data_root(data_root(), name)
+    }
+
+    pub fn from_data_root<P: AsRef<Path>>(root: P, name: &str) -> Result<Instance, InstanceError> {
+        Instance::from_data_root_with_options(root, name, &LoadOptions::default())
+    }
+
+    pub fn from_data_root_with_options<P: AsRef<Path>>(
+        root: P,
+        name: &str,
+        options: &LoadOptions,
+    ) -> Result<Instance, InstanceError> {
+        let instance_path = root.as_ref().join(format!("{}.tsp", name));
+        let atsp_path = root.as_ref().join(format!("{}.atsp", name));
+        // Plain or compressed, symmetric instances first
+        let existing_path = compression::existing_variant(&instance_path)
+            .or_else(|| compression::existing_variant(&atsp_path));
+        Instance::from_tsp_file_with_options(existing_path.unwrap_or(instance_path), options)
+    }
+
+    pub fn from_tsp_file<P: AsRef<Path>>(path: P) -> Result<Instance, InstanceError> {
+        Instance::from_tsp_file_with_options(path, &LoadOptions::default())
+    }
+
+    pub fn from_tsp_file_with_options<P: AsRef<Path>>(path: P, options: &LoadOptions) -> Result<Instance, InstanceError> {
+        // Pick up the optimal tour lying next to the instance, if there is one
+        let solution_path = compression::strip_compression_extension(path.as_ref()).with_extension("opt.tour");
+        let solution_path = compression::existing_variant(&solution_path);
+        Instance::from_files_with_options(path.as_ref().to_path_buf(), solution_path, options)
+    }
+
+    pub fn from_files<P: AsRef<Path>>(tsp_path: P, opt_tour_path: Option<P>) -> Result<Instance, InstanceError> {
+        Instance::from_files_with_options(tsp_path, opt_tour_path, &LoadOptions::default())
+    }
+
+    pub fn from_files_with_options<P: AsRef<Path>>(
+        tsp_path: P,
+        opt_tour_path: Option<P>,
+        options: &LoadOptions,
+    ) -> Result<Instance, InstanceError> {
+        let tsp_path = tsp_path.as_ref();
+        let opt_tour_path = opt_tour_path.as_ref().map(|path| path.as_ref());
+        let Some(cache_dir) = &options.cache_dir else {
+            return Instance::_load_files(tsp_path, opt_tour_path, options);
+        };
+
+        let sources = cache::CacheSources::new(tsp_path, opt_tour_path);
+        let cache_path = cache::cache_path(cache_dir, &sources);
+        if Instance::_is_cache_fresh(&cache_path, tsp_path, opt_tour_path) {
+            let load_options = LoadOptions { cache_dir: None, ..options.clone() };
+            match cache::read_cache(&cache_path) {
+                Ok((instance, cached_sources, cached_options))
+                    if cached_sources == sources && cached_options == load_options =>
+                {
+                    return Ok(instance);
+                }
+                Ok(_) => {}
+                Err(error) => eprintln!("Ignoring cache {}: {}", cache_path.display(), error),
+            }
+        }
+        let instance = Instance::_load_files(tsp_path, opt_tour_path, options)?;
+        let written = fs::create_dir_all(cache_dir)
+            .map_err(|source| InstanceError::Io { path: cache_dir.clone(), source })
+            .and_then(|_| cache::write_cache(&cache_path, &instance, &sources, options));
+        if let Err(error) = written {
+            eprintln!("Cannot cache instance {:?}: {}", instance.name, error);
+        }
+        Ok(instance)
+    }
+
+    /// Reads an instance written by `cache::write_cache`.
+    pub fn from_cache<P: AsRef<Path>>(cache_path: P) -> Result<Instance, InstanceError> {
+        cache::read_cache(cache_path).map(|(instance, _, _)| instance)
+    }
+
+    // A cache is only used when it is newer than the files it was built from
+    fn _is_cache_fresh(cache_path: &Path, tsp_path: &Path, opt_tour_path: Option<&Path>) -> bool {
+        let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        let Some(cache_modified) = modified(cache_path) else {
+            return false;
+        };
+        [Some(tsp_path), opt_tour_path].into_iter()
+            .flatten()
+            .all(|path| modified(path).is_some_and(|source_modified| source_modified <= cache_modified))
+    }
+
+    fn _load_files(tsp_path: &Path, opt_tour_path: Option<&Path>, options: &LoadOptions) -> Result<Instance, InstanceError> {
         println!("Loading instance");
-        let instance_path = vec![DATA_PATH, name, ".tsp"].join("");
-        let solution_path = vec![DATA_PATH, name, ".opt.tour"].join("");
-        let (adjacency_matrix, city_coords) = Instance::load_instance(&instance_path);
-        let optimal_solution = Instance::load_optimal_solution(&solution_path);
-        let name = name.to_string();
+        let parsed = Instance::load_instance(tsp_path, options)?;
+        let dimension = parsed.distances.dimension();
+        let optimal_solution = match opt_tour_path {
+            Some(path) => Some(tour::read_tour(path, &parsed.node_ids)?),
+            None => None,
+        };
+        // The NAME keyword wins over the file name
+        let name = parsed.header.name.clone()
+            .filter(|name| !name.is_empty())
+            .or_else(|| {
+                compression::strip_compression_extension(tsp_path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
         println!("Instance {:?} loaded.", &name);
-        println!("Dimensions = {:?}", city_coords.len());
-        Instance {
+        println!("Dimensions = {:?}", dimension);
+        Ok(Instance {
+            known_optimal_length: tsplib::known_optimal_length(&name),
             name,
+            header: parsed.header,
+            node_ids: Arc::new(parsed.node_ids),
             optimal_solution,
-            adjacency_matrix,
-            city_coords,
-        }
+            distances: Arc::new(parsed.distances),
+            city_coords: parsed.city_coords,
+            city_z_coords: parsed.city_z_coords,
+            edge_weight_type: parsed.edge_weight_type,
+            is_symmetric: parsed.is_symmetric,
+            rounding: options.rounding,
+            best_known_length: None,
+            lower_bound: OnceLock::new(),
+            candidates: None,
+        })
     }
 
-    fn _adjacency_matrix(coordinates: &Vec<(f64, f64)>) -> Vec<Vec<f64>> {
-        let mut adjacency_matrix = vec![vec![0.0; coordinates.len()]; coordinates.len()];
-        for i in 0..coordinates.len() {
-            for j in i + 1..coordinates.len() {
-                let distance = utils::euclidean_distance(coordinates[i], coordinates[j]);
-                // Symetric problem
-                adjacency_matrix[i][j] = distance;
-                adjacency_matrix[j][i] = distance;
-            }
+    /// Builds a symmetric instance from planar coordinates, e.g. generated ones.
+    /// `EXPLICIT` is rejected, as its distances do not come from coordinates.
+    pub fn from_coords(
+        name: &str,
+        city_coords: Vec<(f64, f64)>,
+        edge_weight_type: EdgeWeightType,
+        options: &LoadOptions,
+    ) -> Result<Instance, InstanceError> {
+        if edge_weight_type == EdgeWeightType::Explicit {
+            return Err(InstanceError::UnsupportedEdgeWeightType(edge_weight_type.keyword().to_string()));
         }
-        adjacency_matrix
+        let distances = Instance::_coordinate_distances(&city_coords, None, edge_weight_type, options);
+        let header = TsplibHeader {
+            name: Some(name.to_string()),
+            problem_type: Some(ProblemType::Tsp),
+            dimension: Some(city_coords.len()),
+            edge_weight_type: Some(edge_weight_type),
+            ..TsplibHeader::default()
+        };
+        Ok(Instance {
+            name: name.to_string(),
+            header,
+            node_ids: Arc::new(NodeIds::sequential(city_coords.len())),
+            optimal_solution: None,
+            distances: Arc::new(distances),
+            city_coords: Some(city_coords),
+            city_z_coords: None,
+            edge_weight_type,
+            is_symmetric: true,
+            rounding: options.rounding,
+            known_optimal_length: None,
+            best_known_length: None,
+            lower_bound: OnceLock::new(),
+            candidates: None,
+        })
     }
 
-    fn load_optimal_solution(path: &str) -> Vec<i32> {
-        let file = File::open(path).expect("Failed to open file");
-        let reader = BufReader::new(file);
-        let mut optimal_solution = Vec::new();
-        let mut is_reading_tour = false;
+    /// Builds an instance from precomputed distances, e.g. road network shortest paths,
+    /// `rounding` being the one already applied to them. Asymmetric matrices give asymmetric instances.
+    pub fn from_distance_matrix(name: &str, matrix: DistanceMatrix, rounding: RoundingMode) -> Instance {
+        let dimension = matrix.dimension();
+        let distances = Distances::Matrix(matrix);
+        let is_symmetric = Instance::_is_symmetric(&distances);
+        let header = TsplibHeader {
+            name: Some(name.to_string()),
+            problem_type: Some(if is_symmetric { ProblemType::Tsp } else { ProblemType::Atsp }),
+            dimension: Some(dimension),
+            edge_weight_type: Some(EdgeWeightType::Explicit),
+            edge_weight_format: Some(EdgeWeightFormat::FullMatrix),
+            ..TsplibHeader::default()
+        };
+        Instance {
+            name: name.to_string(),
+            header,
+            node_ids: Arc::new(NodeIds::sequential(dimension)),
+            optimal_solution: None,
+            distances: Arc::new(distances),
+            city_coords: None,
+            city_z_coords: None,
+            edge_weight_type: EdgeWeightType::Explicit,
+            is_symmetric,
+            rounding,
+            known_optimal_length: None,
+            best_known_length: None,
+            lower_bound: OnceLock::new(),
+            candidates: None,
+        }
+    }
 
-        for line in reader.lines() {
-            let line = line.expect("Failed to read line");
-            let parts: Vec<&str> = line.trim().split_whitespace().collect();
+    /// Saves the instance as a TSPLIB `.tsp` file: node coordinates when it
+    /// has them, a full distance matrix otherwise.
+    pub fn save_tsp_file<P: AsRef<Path>>(&self, path: P) -> Result<(), InstanceError> {
+        let path = path.as_ref();
+        let to_error = |source| InstanceError::Io { path: path.to_path_buf(), source };
+        let mut writer = BufWriter::new(File::create(path).map_err(to_error)?);
 
-            if parts.len() == 1 {
-                if parts[0] == "TOUR_SECTION" {
-                    is_reading_tour = true;
-                    continue;
+        writeln!(writer, "NAME : {}", self.name).map_err(to_error)?;
+        for comment in &self.header.comments {
+            writeln!(writer, "COMMENT : {}", comment).map_err(to_error)?;
+        }
+        writeln!(writer, "TYPE : {}", if self.is_symmetric { "TSP" } else { "ATSP" }).map_err(to_error)?;
+        writeln!(writer, "DIMENSION : {}", self.dimension()).map_err(to_error)?;
+        match &self.city_coords {
+            Some(city_coords) if self.edge_weight_type != EdgeWeightType::Explicit => {
+                writeln!(writer, "EDGE_WEIGHT_TYPE : {}", self.edge_weight_type.keyword()).map_err(to_error)?;
+                writeln!(writer, "NODE_COORD_SECTION").map_err(to_error)?;
+                for (i, (x, y)) in city_coords.iter().enumerate() {
+                    match &self.city_z_coords {
+                        Some(z) => writeln!(writer, "{} {} {} {}", self.node_id(i), x, y, z[i]),
+                        None => writeln!(writer, "{} {} {}", self.node_id(i), x, y),
+                    }.map_err(to_error)?;
                 }
-                if is_reading_tour {
-                    let id = parts[0].parse::<i32>().expect("Invalid ID format");
-                    if id == -1 {
-                        is_reading_tour = false;
-                        continue;
-                    }
-                    optimal_solution.push(id);
+            }
+            _ => {
+                writeln!(writer, "EDGE_WEIGHT_TYPE : EXPLICIT").map_err(to_error)?;
+                writeln!(writer, "EDGE_WEIGHT_FORMAT : FULL_MATRIX").map_err(to_error)?;
+                writeln!(writer, "EDGE_WEIGHT_SECTION").map_err(to_error)?;
+                for i in 0..self.dimension() {
+                    let row: Vec<String> = (0..self.dimension()).map(|j| self.distance(i, j).to_string()).collect();
+                    writeln!(writer, "{}", row.join(" ")).map_err(to_error)?;
                 }
             }
         }
-        optimal_solution
+        writeln!(writer, "EOF").map_err(to_error)?;
+        writer.flush().map_err(to_error)
     }
 
-    fn load_instance(path: &str) -> (Vec<Vec<f64>>, Vec<(f64, f64)>) {
-        let file = File::open(path).expect("Failed to open file");
-        let reader = BufReader::new(file);
-        let mut coords = HashMap::new();
-        let mut coords_section: bool = false;
-        let mut dimension = 0;
+    pub fn set_best_known_length(&mut self, length: f64) {
+        self.best_known_length = Some(length);
+    }
 
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let parts: Vec<&str> = line.split_whitespace().collect();
+    /// Reads the best known length of this instance from a TSPLIB style
+    /// solutions file made of `name : length` lines. Bounded entries given
+    /// as `[lower, upper]` use the upper bound, i.e. the best tour known.
+    pub fn load_best_known_length<P: AsRef<Path>>(&mut self, solutions_path: P) -> Result<(), InstanceError> {
+        let path = solutions_path.as_ref();
+        let reader = Instance::open(path)?;
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })?;
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            if name.trim() != self.name {
+                continue;
+            }
+            let value = value.trim().trim_start_matches('[').trim_end_matches(']');
+            let best_known = value.rsplit(',').next().unwrap_or_default().split_whitespace().next();
+            let length = best_known.and_then(|length| length.parse::<f64>().ok())
+                .ok_or_else(|| InstanceError::InvalidHeader {
+                    line: line_number + 1,
+                    keyword: self.name.clone(),
+                    value: value.to_string(),
+                })?;
+            self.best_known_length = Some(length);
+        }
+        Ok(())
+    }
 
-            if line.starts_with("EDGE_WEIGHT_TYPE") {
-                assert!(parts.last().unwrap() == &"EUC_2D", 
-                "Use only instances with EUC_2D weight type")
+    /// Checks that the loaded optimal tour reproduces the published optimum.
+    /// Only meaningful with `RoundingMode::Nint`, which is how the optima were
+    /// computed, so other roundings always pass.
+    pub fn validate_optimal_solution(&self) -> Result<(), InstanceError> {
+        if self.rounding != RoundingMode::Nint {
+            return Ok(());
+        }
+        if let (Some(expected), Some(optimal_solution)) = (self.known_optimal_length, &self.optimal_solution) {
+            let found = self.get_solution_distance(optimal_solution);
+            if (found - expected).abs() > 1e-6 {
+                return Err(InstanceError::OptimumMismatch { expected, found });
             }
-            else if line.starts_with("DIMENSION") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                dimension = parts.last().unwrap().parse::<usize>().unwrap();
+        }
+        Ok(())
+    }
+
+    /// Reads a tour of this instance from a TSPLIB `.tour` file, e.g. to warm start an algorithm.
+    pub fn load_tour<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u32>, InstanceError> {
+        tour::read_tour(path, &self.node_ids)
+    }
+
+    /// Saves a tour of this instance as a TSPLIB `.tour` file, noting its length in the comment.
+    pub fn save_tour<P: AsRef<Path>>(&self, path: P, solution: &[u32]) -> Result<(), InstanceError> {
+        let comment = format!("Length {}", self.get_solution_distance(solution));
+        tour::write_tour(path, &self.name, Some(&comment), solution, &self.node_ids)
+    }
+
+    /// Node id of a city as given in the instance file.
+    pub fn node_id(&self, city: usize) -> usize {
+        self.node_ids.id(city)
+    }
+
+    /// City index of a node id from the instance file.
+    pub fn city_index(&self, node_id: usize) -> Option<usize> {
+        self.node_ids.index(node_id)
+    }
+
+    pub fn node_ids(&self) -> &NodeIds {
+        &self.node_ids
+    }
+
+    /// Replaces the node ids of the cities, e.g. for instances built from other formats.
+    pub fn set_node_ids(&mut self, node_ids: NodeIds) {
+        assert_eq!(node_ids.len(), self.dimension(), "Node ids have different dimensionality than instance!");
+        self.node_ids = Arc::new(node_ids);
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.distances.dimension()
+    }
+
+    /// Cost of going from city `i` to city `j` (0-based indices).
+    #[inline]
+    pub fn distance(&self, i: usize, j: usize) -> f64 {
+        self.distances.distance(i, j)
+    }
+
+    pub fn distances(&self) -> &Distances {
+        &self.distances
+    }
+
+    /// The precomputed matrix, `None` when distances are computed on the fly.
+    pub fn distance_matrix(&self) -> Option<&DistanceMatrix> {
+        match self.distances.as_ref() {
+            Distances::Matrix(matrix) => Some(matrix),
+            Distances::Lazy(_) => None,
+        }
+    }
+
+    pub fn candidate_lists(&self) -> Option<&CandidateLists> {
+        self.candidates.as_deref()
+    }
+
+    pub fn set_candidate_lists(&mut self, candidates: CandidateLists) {
+        self.cache_neighbour_distances(candidates.as_slice());
+        self.candidates = Some(Arc::new(candidates));
+    }
+
+    /// Precomputes the `k` nearest neighbours of every city as candidate lists.
+    pub fn build_nearest_neighbour_candidates(&mut self, k: usize) {
+        let candidates = CandidateLists::nearest_neighbours(self, k);
+        self.set_candidate_lists(candidates);
+    }
+
+    /// Precomputes the `k` nearest neighbours of every city in each quadrant as candidate lists.
+    pub fn build_quadrant_candidates(&mut self, k: usize) {
+        let candidates = CandidateLists::quadrant_neighbours(self, k);
+        self.set_candidate_lists(candidates);
+    }
+
+    /// Uses the Delaunay graph of the city coordinates as candidate lists,
+    /// falling back to the `k` nearest neighbours for instances without planar coordinates.
+    pub fn build_delaunay_candidates(&mut self, k: usize) {
+        let candidates = CandidateLists::delaunay(self)
+            .unwrap_or_else(|| CandidateLists::nearest_neighbours(self, k));
+        self.set_candidate_lists(candidates);
+    }
+
+    /// Precomputes the `k` alpha-nearest neighbours of every city as candidate lists.
+    pub fn build_alpha_candidates(&mut self, k: usize, ascent_iterations: usize) {
+        let candidates = CandidateLists::alpha_nearness(self, k, ascent_iterations);
+        self.set_candidate_lists(candidates);
+    }
+
+    /// Caches the distances to the given neighbours of every city when they are computed on the fly.
+    pub fn cache_neighbour_distances(&mut self, neighbours: &[Vec<u32>]) {
+        // Checked before `make_mut`, which would copy a shared matrix
+        if !matches!(self.distances.as_ref(), Distances::Lazy(_)) {
+            return;
+        }
+        if let Distances::Lazy(lazy) = Arc::make_mut(&mut self.distances) {
+            lazy.cache_neighbours(neighbours);
+        }
+    }
+
+    // Compressed files are decompressed on the fly
+    pub(crate) fn open(path: &Path) -> Result<Box<dyn BufRead>, InstanceError> {
+        compression::open_reader(path)
+    }
+
+    pub(crate) fn _coordinate_distances(
+        coordinates: &[(f64, f64)],
+        z_coordinates: Option<&[f64]>,
+        edge_weight_type: EdgeWeightType,
+        options: &LoadOptions,
+    ) -> Distances {
+        let points: Vec<(f64, f64, f64)> = coordinates.iter()
+            .enumerate()
+            .map(|(i, &(x, y))| (x, y, z_coordinates.map_or(0.0, |z| z[i])))
+            .collect();
+        if points.len() > options.lazy_threshold {
+            return Distances::Lazy(LazyDistances::new(points, edge_weight_type, options.rounding));
+        }
+        // Symetric problem
+        let matrix = DistanceMatrix::from_symmetric_fn(points.len(), options.triangular, options.precision, |i, j| {
+            options.rounding.apply(edge_weight_type.distance(points[i], points[j]))
+        });
+        Distances::Matrix(matrix)
+    }
+
+    fn _explicit_adjacency_matrix(
+        format: EdgeWeightFormat,
+        dimension: usize,
+        weights: &[f64],
+        options: &LoadOptions,
+    ) -> Result<DistanceMatrix, InstanceError> {
+        let entries = format.entries(dimension);
+        if entries.len() != weights.len() {
+            return Err(InstanceError::EdgeWeightCount { expected: entries.len(), found: weights.len() });
+        }
+        let triangular = options.triangular && format.is_triangular();
+        let mut distance_matrix = DistanceMatrix::new(dimension, triangular, options.precision);
+        for (&(i, j), &weight) in entries.iter().zip(weights) {
+            if format.is_triangular() {
+                distance_matrix.set_symmetric(i, j, weight);
+            } else {
+                distance_matrix.set(i, j, weight);
+            }
+        }
+        Ok(distance_matrix)
+    }
+
+    fn load_instance(path: &Path, options: &LoadOptions) -> Result<ParsedInstance, InstanceError> {
+        let reader = Instance::open(path)?;
+        let mut coords = Vec::new();
+        let mut display_coords = Vec::new();
+        let mut weights = Vec::new();
+        let mut section = Section::Header;
+        let mut header = TsplibHeader::default();
+
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })?;
+            let line_number = line_number + 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
             }
-            else if line.trim() == "NODE_COORD_SECTION" {
-                coords_section = true;
+
+            // Keywords and section names start with a letter, section data never does
+            if trimmed.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                let (keyword, value) = match trimmed.split_once(':') {
+                    Some((keyword, value)) => (keyword.trim(), value.trim()),
+                    None => (trimmed, ""),
+                };
+                section = match keyword {
+                    "NODE_COORD_SECTION" => Section::NodeCoords,
+                    "EDGE_WEIGHT_SECTION" => Section::EdgeWeights,
+                    "DISPLAY_DATA_SECTION" => Section::DisplayData,
+                    "EOF" => break,
+                    _ if value.is_empty() => Section::Other,
+                    _ => {
+                        header.set(keyword, value, line_number)?;
+                        Section::Header
+                    }
+                };
                 continue;
             }
 
-            if coords_section && parts.len() == 3 {
-                let id = parts[0].parse::<usize>().expect("Invalid ID format");
-                let x = parts[1].parse::<f64>().expect("Invalid X coordinate format");
-                let y = parts[2].parse::<f64>().expect("Invalid Y coordinate format");
-                coords.insert(id, (x, y));
+            let parts: Vec<&str> = trimmed.split_whitespace().collect();
+            match section {
+                Section::NodeCoords | Section::DisplayData => {
+                    let invalid_coordinate = || InstanceError::InvalidCoordinate {
+                        line: line_number,
+                        content: line.clone(),
+                    };
+                    // Display data is always planar, node coordinates follow the edge weight type
+                    let is_3d = section == Section::NodeCoords
+                        && header.edge_weight_type.is_some_and(|edge_weight_type| edge_weight_type.is_3d());
+                    if parts.len() != if is_3d { 4 } else { 3 } {
+                        return Err(invalid_coordinate());
+                    }
+                    let id = parts[0].parse::<usize>().map_err(|_| invalid_coordinate())?;
+                    let x = parts[1].parse::<f64>().map_err(|_| invalid_coordinate())?;
+                    let y = parts[2].parse::<f64>().map_err(|_| invalid_coordinate())?;
+                    let z = match parts.get(3) {
+                        Some(z) => z.parse::<f64>().map_err(|_| invalid_coordinate())?,
+                        None => 0.0,
+                    };
+                    if section == Section::NodeCoords {
+                        coords.push((id, (x, y, z)));
+                    } else {
+                        display_coords.push((id, (x, y, z)));
+                    }
+                }
+                Section::EdgeWeights => {
+                    // Matrix rows may be wrapped over any number of lines
+                    for part in parts {
+                        let weight = part.parse::<f64>().map_err(|_| InstanceError::InvalidEdgeWeight {
+                            line: line_number,
+                            content: line.clone(),
+                        })?;
+                        weights.push(weight);
+                    }
+                }
+                _ => {}
             }
         }
 
-        let city_coords: Vec<(f64, f64)> = (1..=dimension)
-            .map(|i| coords[&i])
-            .collect();
+        let is_asymmetric_problem = match header.problem_type {
+            None | Some(ProblemType::Tsp) => false,
+            Some(ProblemType::Atsp) => true,
+            Some(other) => return Err(InstanceError::UnsupportedProblemType(other.keyword().to_string())),
+        };
+        let dimension = header.dimension
+            .ok_or_else(|| InstanceError::MissingKeyword("DIMENSION".to_string()))?;
+        let edge_weight_type = header.edge_weight_type
+            .ok_or_else(|| InstanceError::MissingKeyword("EDGE_WEIGHT_TYPE".to_string()))?;
+        let edge_weight_format = header.edge_weight_format;
+        // Rows of explicit matrices are always numbered 1..=dimension. Their
+        // coordinates come from the node section when there is one, and from
+        // the display data otherwise.
+        let (node_ids, points) = match edge_weight_type {
+            EdgeWeightType::Explicit => {
+                let explicit_coords = if coords.is_empty() { display_coords } else { coords };
+                if explicit_coords.is_empty() {
+                    (NodeIds::sequential(dimension), None)
+                } else {
+                    let (coord_ids, points) = Instance::_ordered_coords(explicit_coords, dimension)?;
+                    if let NodeIds::Mapped { ids, .. } = coord_ids {
+                        let missing = (1..=dimension).find(|id| !ids.contains(id)).unwrap_or(dimension);
+                        return Err(InstanceError::MissingNode(missing));
+                    }
+                    (NodeIds::sequential(dimension), Some(points))
+                }
+            }
+            _ => {
+                let (node_ids, points) = Instance::_ordered_coords(coords, dimension)?;
+                (node_ids, Some(points))
+            }
+        };
+        let city_coords: Option<Vec<(f64, f64)>> = points.as_ref()
+            .map(|points| points.iter().map(|&(x, y, _)| (x, y)).collect());
+        let city_z_coords: Option<Vec<f64>> = points.as_ref()
+            .filter(|_| edge_weight_type.is_3d())
+            .map(|points| points.iter().map(|&(_, _, z)| z).collect());
 
-        let adjacency_matrix: Vec<Vec<f64>> = Instance::_adjacency_matrix(&city_coords);
+        let distances = match (edge_weight_type, &city_coords) {
+            (EdgeWeightType::Explicit, _) => {
+                let format = edge_weight_format
+                    .ok_or_else(|| InstanceError::UnsupportedEdgeWeightFormat(String::new()))?;
+                if format == EdgeWeightFormat::Function {
+                    return Err(InstanceError::UnsupportedEdgeWeightFormat("FUNCTION".to_string()));
+                }
+                Distances::Matrix(Instance::_explicit_adjacency_matrix(format, dimension, &weights, options)?)
+            }
+            (_, Some(city_coords)) => {
+                Instance::_coordinate_distances(city_coords, city_z_coords.as_deref(), edge_weight_type, options)
+            }
+            (_, None) => unreachable!("coordinate based instances always have coordinates"),
+        };
+
+        // Only a full matrix can hold different costs for the two directions
+        let is_symmetric = !is_asymmetric_problem
+            || edge_weight_format != Some(EdgeWeightFormat::FullMatrix)
+            || Instance::_is_symmetric(&distances);
 
-        (adjacency_matrix, city_coords)
+        Ok(ParsedInstance {
+            header,
+            node_ids,
+            edge_weight_type,
+            distances,
+            city_coords,
+            city_z_coords,
+            is_symmetric,
+        })
     }
 
-    pub fn get_solution_distance(&self, solution: &Vec<i32>) -> f64 {
+    fn _is_symmetric(distances: &Distances) -> bool {
+        (0..distances.dimension()).all(|i| {
+            (0..i).all(|j| distances.distance(i, j) == distances.distance(j, i))
+        })
+    }
+
+    fn _ordered_coords(
+        mut coords: Vec<(usize, Point)>,
+        dimension: usize,
+    ) -> Result<(NodeIds, Vec<Point>), InstanceError> {
+        coords.sort_by_key(|&(id, _)| id);
+        let node_ids = NodeIds::from_sorted_ids(coords.iter().map(|&(id, _)| id).collect())?;
+        if coords.len() != dimension {
+            return Err(InstanceError::DimensionMismatch { expected: dimension, found: coords.len() });
+        }
+        Ok((node_ids, coords.into_iter().map(|(_, point)| point).collect()))
+    }
+
+    /// Length of the closed tour visiting `solution` in order, following arc directions on ATSP instances.
+    pub fn get_solution_distance(&self, solution: &[u32]) -> f64 {
         let mut dist = 0.0;
-        assert_eq!(solution.len(), self.city_coords.len(), "Solution has different dimensionality than instance!");
-        for i in 0..solution.len() - 1 {
-            dist += self.adjacency_matrix[(solution[i] - 1) as usize][(solution[i+1] - 1) as usize];
+        assert_eq!(solution.len(), self.dimension(), "Solution has different dimensionality than instance!");
+        for edge in solution.windows(2) {
+            dist += self.distance(edge[0] as usize, edge[1] as usize);
         }
-        dist += self.adjacency_matrix[(solution[solution.len() - 1] - 1) as usize][(solution[0] - 1) as usize];
+        dist += self.distance(solution[solution.len() - 1] as usize, solution[0] as usize);
         dist
     }
 
-    pub fn get_solution_score(&self, solution: &Vec<i32>) -> f64 {
-        // Scores given solution relatively to optimal solution distance
-        let optimal_solution_distance = self.get_solution_distance(&self.optimal_solution);
-        let solution_distance = self.get_solution_distance(&solution);
-        return solution_distance / optimal_solution_distance;
+    /// Change in tour length from swapping the cities at positions `a` and `b`,
+    /// computed in O(1) from the (at most four) arcs entering and leaving them.
+    /// Arc directions are kept, so it holds for ATSP instances as well.
+    pub fn swap_delta(&self, solution: &[u32], a: usize, b: usize) -> f64 {
+        let n = solution.len();
+        if a == b {
+            return 0.0;
+        }
+        // Arcs are identified by the position they start from; adjacent
+        // positions and the wrap-around share arcs, which must count once
+        let mut arcs = [(a + n - 1) % n, a, (b + n - 1) % n, b];
+        arcs.sort_unstable();
+        let city_after_swap = |position: usize| match position {
+            _ if position == a => solution[b],
+            _ if position == b => solution[a],
+            _ => solution[position],
+        };
+        let mut delta = 0.0;
+        for (index, &from) in arcs.iter().enumerate() {
+            if index > 0 && arcs[index - 1] == from {
+                continue;
+            }
+            let to = (from + 1) % n;
+            delta += self.distance(city_after_swap(from) as usize, city_after_swap(to) as usize)
+                - self.distance(solution[from] as usize, solution[to] as usize);
+        }
+        delta
+    }
+
+    /// Change in tour length from reversing the segment between positions `a`
+    /// and `b` (inclusive), the 2-opt move. O(1) on symmetric instances, where
+    /// only the two arcs around the segment change; O(segment length) on ATSP
+    /// instances, where the arcs inside the segment change direction.
+    pub fn reversal_delta(&self, solution: &[u32], a: usize, b: usize) -> f64 {
+        let n = solution.len();
+        let (i, j) = (a.min(b), a.max(b));
+        let arc = |from: usize, to: usize| self.distance(solution[from] as usize, solution[to] as usize);
+        // Arcs inside the segment, travelled backwards after the reversal
+        let inner_delta = || (i..j).map(|k| arc(k + 1, k) - arc(k, k + 1)).sum::<f64>();
+        if i == j {
+            return 0.0;
+        }
+        if j - i + 1 == n {
+            // Reversing the whole tour only flips the direction of every arc
+            return if self.is_symmetric { 0.0 } else { inner_delta() + arc(i, j) - arc(j, i) };
+        }
+        let (before, after) = ((i + n - 1) % n, (j + 1) % n);
+        let boundary_delta = arc(before, j) + arc(i, after) - arc(before, i) - arc(j, after);
+        if self.is_symmetric {
+            boundary_delta
+        } else {
+            boundary_delta + inner_delta()
+        }
+    }
+
+    /// Lower bound on the length of any tour, computed once. Uses the Held-Karp
+    /// bound when the distances are in memory, with fewer subgradient steps as
+    /// instances grow, and the cheap edge-based bounds for instances too large
+    /// for a distance matrix. Those take the nearest neighbours from a KD-tree
+    /// on planar instances, and only scan as many cities as the distance
+    /// lookup budget allows otherwise, which weakens the bound on huge instances.
+    pub fn lower_bound(&self) -> f64 {
+        *self.lower_bound.get_or_init(|| {
+            let distances = self.distances.as_ref();
+            let dimension = distances.dimension();
+            let scanned_cities = LOWER_BOUND_DISTANCE_BUDGET / dimension.max(1);
+            let cheap_bound = if !self.is_symmetric {
+                bounds::partial_cheapest_arcs_bound(distances, scanned_cities)
+            } else if CandidateLists::planar_coords(self).is_some() {
+                let neighbours = CandidateLists::nearest_neighbours(self, 2);
+                bounds::nearest_neighbours_bound(distances, neighbours.as_slice())
+            } else {
+                bounds::partial_two_cheapest_edges_bound(distances, scanned_cities)
+            };
+            if let Distances::Lazy(_) = distances {
+                return cheap_bound;
+            }
+            let iterations = HELD_KARP_ITERATIONS.min(LOWER_BOUND_DISTANCE_BUDGET / (dimension * dimension).max(1));
+            if iterations == 0 {
+                return cheap_bound;
+            }
+            let held_karp_bound = if self.is_symmetric {
+                bounds::held_karp_bound(distances, iterations)
+            } else {
+                bounds::held_karp_bound(&SymmetricDistances(distances), iterations)
+            };
+            cheap_bound.max(held_karp_bound)
+        })
+    }
+
+    /// Length solutions are scored against, falling back from the optimal tour
+    /// to the published optimum, the best known length and finally a lower bound.
+    /// Published optima use `nint` distances, so they only apply under `Nint` rounding.
+    pub fn score_reference(&self) -> (ScoreReference, f64) {
+        let known_optimal_length = self.known_optimal_length
+            .filter(|_| self.rounding == RoundingMode::Nint);
+        if let Some(optimal_solution) = &self.optimal_solution {
+            (ScoreReference::OptimalTour, self.get_solution_distance(optimal_solution))
+        } else if let Some(length) = known_optimal_length {
+            (ScoreReference::KnownOptimum, length)
+        } else if let Some(length) = self.best_known_length {
+            (ScoreReference::BestKnown, length)
+        } else {
+            (ScoreReference::LowerBound, self.lower_bound())
+        }
+    }
+
+    /// Relative excess of the solution over the lower bound, meaningful even
+    /// on instances without a known optimum.
+    pub fn get_lower_bound_gap(&self, solution: &[u32]) -> f64 {
+        let lower_bound = self.lower_bound();
+        (self.get_solution_distance(solution) - lower_bound) / lower_bound
     }
 
+    pub fn get_solution_score(&self, solution: &[u32]) -> f64 {
+        // Scores given solution relatively to the best available reference distance
+        let (_, reference_distance) = self.score_reference();
+        let solution_distance = self.get_solution_distance(solution);
+        solution_distance / reference_distance
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    // Integer weights keep the deltas and the full evaluations exact
+    fn random_instance(rng: &mut StdRng, dimension: usize, symmetric: bool) -> Instance {
+        let mut matrix = DistanceMatrix::new(dimension, false, MatrixPrecision::F64);
+        for i in 0..dimension {
+            for j in 0..dimension {
+                if i != j && (!symmetric || i < j) {
+                    let weight = rng.gen_range(1..100) as f64;
+                    matrix.set(i, j, weight);
+                    if symmetric {
+                        matrix.set(j, i, weight);
+                    }
+                }
+            }
+        }
+        Instance::from_distance_matrix("random", matrix, RoundingMode::Nint)
+    }
+
+    // Compares the delta of the move at every pair of positions, in both
+    // orders and including equal ones, with a full evaluation of the moved tour
+    fn assert_deltas_match(symmetric: bool) {
+        let mut rng = StdRng::seed_from_u64(if symmetric { 3 } else { 5 });
+        for dimension in 2..=9 {
+            for _ in 0..5 {
+                let instance = random_instance(&mut rng, dimension, symmetric);
+                assert_eq!(instance.is_symmetric, symmetric);
+                let mut solution: Vec<u32> = (0..dimension as u32).collect();
+                solution.shuffle(&mut rng);
+                let distance = instance.get_solution_distance(&solution);
+                for a in 0..dimension {
+                    for b in 0..dimension {
+                        let mut swapped = solution.clone();
+                        swapped.swap(a, b);
+                        let expected = instance.get_solution_distance(&swapped) - distance;
+                        assert_eq!(instance.swap_delta(&solution, a, b), expected, "swap {} {} of {:?}", a, b, solution);
+
+                        let mut reversed = solution.clone();
+                        reversed[a.min(b)..=a.max(b)].reverse();
+                        let expected = instance.get_solution_distance(&reversed) - distance;
+                        assert_eq!(instance.reversal_delta(&solution, a, b), expected, "reversal {} {} of {:?}", a, b, solution);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_deltas_match_full_evaluation() {
+        assert_deltas_match(true);
+    }
+
+    #[test]
+    fn asymmetric_deltas_match_full_evaluation() {
+        assert_deltas_match(false);
+    }
 }
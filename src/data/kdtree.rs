@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// Max-heap entry ordered by distance, so the farthest kept neighbour is on top
+#[derive(Clone, Copy, PartialEq)]
+struct Neighbour {
+    squared_distance: f64,
+    index: u32,
+}
+
+impl Eq for Neighbour {}
+
+impl PartialOrd for Neighbour {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Neighbour {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.squared_distance.total_cmp(&other.squared_distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+/// Static 2D tree over a point set, stored implicitly: the median of every
+/// index range is its root and the two halves are its subtrees.
+pub struct KdTree<'a> {
+    points: &'a [(f64, f64)],
+    order: Vec<u32>,
+}
+
+impl<'a> KdTree<'a> {
+    pub fn new(points: &'a [(f64, f64)]) -> KdTree<'a> {
+        let mut order: Vec<u32> = (0..points.len() as u32).collect();
+        KdTree::build(points, &mut order, 0);
+        KdTree { points, order }
+    }
+
+    fn coordinate(point: (f64, f64), depth: usize) -> f64 {
+        if depth.is_multiple_of(2) { point.0 } else { point.1 }
+    }
+
+    fn build(points: &[(f64, f64)], order: &mut [u32], depth: usize) {
+        if order.len() <= 1 {
+            return;
+        }
+        let median = order.len() / 2;
+        order.select_nth_unstable_by(median, |&a, &b| {
+            KdTree::coordinate(points[a as usize], depth)
+                .total_cmp(&KdTree::coordinate(points[b as usize], depth))
+        });
+        let (lower, upper) = order.split_at_mut(median);
+        KdTree::build(points, lower, depth + 1);
+        KdTree::build(points, &mut upper[1..], depth + 1);
+    }
+
+    /// Indices of the `k` points closest to `query` (Euclidean) accepted by `filter`, nearest first.
+    pub fn nearest<F>(&self, query: (f64, f64), k: usize, filter: F) -> Vec<u32>
+    where
+        F: Fn(u32) -> bool,
+    {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(0, self.order.len(), 0, query, k, &filter, &mut heap);
+        }
+        heap.into_sorted_vec().into_iter().map(|neighbour| neighbour.index).collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search<F>(
+        &self,
+        low: usize,
+        high: usize,
+        depth: usize,
+        query: (f64, f64),
+        k: usize,
+        filter: &F,
+        heap: &mut BinaryHeap<Neighbour>,
+    ) where
+        F: Fn(u32) -> bool,
+    {
+        if low >= high {
+            return;
+        }
+        let median = low + (high - low) / 2;
+        let index = self.order[median];
+        let point = self.points[index as usize];
+
+        if filter(index) {
+            let dx = point.0 - query.0;
+            let dy = point.1 - query.1;
+            let candidate = Neighbour { squared_distance: dx * dx + dy * dy, index };
+            if heap.len() < k {
+                heap.push(candidate);
+            } else if candidate < *heap.peek().expect("heap holds k neighbours") {
+                heap.pop();
+                heap.push(candidate);
+            }
+        }
+
+        let difference = KdTree::coordinate(query, depth) - KdTree::coordinate(point, depth);
+        let (near, far) = if difference < 0.0 {
+            ((low, median), (median + 1, high))
+        } else {
+            ((median + 1, high), (low, median))
+        };
+        self.search(near.0, near.1, depth + 1, query, k, filter, heap);
+        // The other side can only help if the splitting plane is closer than the current k-th neighbour
+        let worst = heap.peek().map_or(f64::INFINITY, |neighbour| neighbour.squared_distance);
+        if heap.len() < k || difference * difference < worst {
+            self.search(far.0, far.1, depth + 1, query, k, filter, heap);
+        }
+    }
+}
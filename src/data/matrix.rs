@@ -0,0 +1,108 @@
+/// Element type used to store the distances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatrixPrecision {
+    #[default]
+    F64,
+    F32,
+    // Distances are rounded, so only exact for integral (e.g. nint rounded) weights
+    I32,
+}
+
+#[derive(Clone, Debug)]
+enum MatrixValues {
+    F64(Vec<f64>),
+    F32(Vec<f32>),
+    I32(Vec<i32>),
+}
+
+/// Contiguous row-major distance matrix. Symmetric instances may keep only
+/// the lower triangle (diagonal included), halving the memory footprint.
+#[derive(Clone, Debug)]
+pub struct DistanceMatrix {
+    dimension: usize,
+    triangular: bool,
+    values: MatrixValues,
+}
+
+impl DistanceMatrix {
+    pub fn new(dimension: usize, triangular: bool, precision: MatrixPrecision) -> DistanceMatrix {
+        let len = if triangular { dimension * (dimension + 1) / 2 } else { dimension * dimension };
+        let values = match precision {
+            MatrixPrecision::F64 => MatrixValues::F64(vec![0.0; len]),
+            MatrixPrecision::F32 => MatrixValues::F32(vec![0.0; len]),
+            MatrixPrecision::I32 => MatrixValues::I32(vec![0; len]),
+        };
+        DistanceMatrix { dimension, triangular, values }
+    }
+
+    /// Builds a symmetric matrix from `distance(i, j)`, evaluated once per pair `i < j`.
+    pub fn from_symmetric_fn<F>(dimension: usize, triangular: bool, precision: MatrixPrecision, distance: F) -> DistanceMatrix
+    where
+        F: Fn(usize, usize) -> f64,
+    {
+        let mut matrix = DistanceMatrix::new(dimension, triangular, precision);
+        for i in 0..dimension {
+            for j in i + 1..dimension {
+                matrix.set_symmetric(i, j, distance(i, j));
+            }
+        }
+        matrix
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn is_triangular(&self) -> bool {
+        self.triangular
+    }
+
+    pub fn precision(&self) -> MatrixPrecision {
+        match self.values {
+            MatrixValues::F64(_) => MatrixPrecision::F64,
+            MatrixValues::F32(_) => MatrixPrecision::F32,
+            MatrixValues::I32(_) => MatrixPrecision::I32,
+        }
+    }
+
+    #[inline]
+    fn index(&self, i: usize, j: usize) -> usize {
+        if self.triangular {
+            let (row, column) = if i >= j { (i, j) } else { (j, i) };
+            row * (row + 1) / 2 + column
+        } else {
+            i * self.dimension + j
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        let index = self.index(i, j);
+        match &self.values {
+            MatrixValues::F64(values) => values[index],
+            MatrixValues::F32(values) => values[index] as f64,
+            MatrixValues::I32(values) => values[index] as f64,
+        }
+    }
+
+    /// Sets the cost of going from `i` to `j`. In a triangular matrix this is also the cost from `j` to `i`.
+    pub fn set(&mut self, i: usize, j: usize, value: f64) {
+        let index = self.index(i, j);
+        match &mut self.values {
+            MatrixValues::F64(values) => values[index] = value,
+            MatrixValues::F32(values) => values[index] = value as f32,
+            MatrixValues::I32(values) => values[index] = value.round() as i32,
+        }
+    }
+
+    pub fn set_symmetric(&mut self, i: usize, j: usize, value: f64) {
+        self.set(i, j, value);
+        if !self.triangular {
+            self.set(j, i, value);
+        }
+    }
+
+    pub fn row(&self, i: usize) -> impl Iterator<Item = f64> + '_ {
+        (0..self.dimension).map(move |j| self.get(i, j))
+    }
+}
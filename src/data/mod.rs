@@ -1,2 +1,19 @@
+pub mod bounds;
+pub mod cache;
+pub mod candidates;
+pub mod compression;
+pub mod delaunay;
+pub mod distances;
+pub mod error;
+pub mod generators;
+pub mod import;
 pub mod instance;
-pub mod utils;
\ No newline at end of file
+pub mod kdtree;
+pub mod matrix;
+pub mod node_ids;
+pub mod one_tree;
+pub mod road;
+pub mod tour;
+pub mod transform;
+pub mod tsplib;
+pub mod utils;
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::data::error::InstanceError;
+
+/// Mapping between the node ids of an instance file and the 0-based city
+/// indices used everywhere else. Cities are indexed in increasing id order.
+#[derive(Clone, Debug)]
+pub enum NodeIds {
+    /// The usual TSPLIB numbering, ids `1..=n`
+    Sequential(usize),
+    Mapped { ids: Vec<usize>, indices: HashMap<usize, u32> },
+}
+
+impl NodeIds {
+    pub fn sequential(dimension: usize) -> NodeIds {
+        NodeIds::Sequential(dimension)
+    }
+
+    /// Builds the mapping from sorted ids, failing on the first repeated one.
+    pub fn from_sorted_ids(ids: Vec<usize>) -> Result<NodeIds, InstanceError> {
+        if let Some(pair) = ids.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(InstanceError::DuplicateNode(pair[0]));
+        }
+        if ids.iter().enumerate().all(|(index, &id)| id == index + 1) {
+            return Ok(NodeIds::Sequential(ids.len()));
+        }
+        let indices = ids.iter().enumerate().map(|(index, &id)| (id, index as u32)).collect();
+        Ok(NodeIds::Mapped { ids, indices })
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            NodeIds::Sequential(dimension) => *dimension,
+            NodeIds::Mapped { ids, .. } => ids.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Node id of the city with the given index.
+    pub fn id(&self, index: usize) -> usize {
+        match self {
+            NodeIds::Sequential(_) => index + 1,
+            NodeIds::Mapped { ids, .. } => ids[index],
+        }
+    }
+
+    /// Index of the city with the given node id, if there is one.
+    pub fn index(&self, id: usize) -> Option<usize> {
+        match self {
+            NodeIds::Sequential(dimension) => (1..=*dimension).contains(&id).then(|| id - 1),
+            NodeIds::Mapped { indices, .. } => indices.get(&id).map(|&index| index as usize),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use rayon::prelude::*;
+
+use crate::data::distances::DistanceProvider;
+
+// Node left out of the spanning tree and joined to it by its two cheapest edges
+const SPECIAL_NODE: usize = 0;
+
+/// Views asymmetric distances as symmetric ones by taking the cheaper
+/// direction of every edge, so 1-trees still bound directed tours from below.
+pub struct SymmetricDistances<'a, D: DistanceProvider + ?Sized>(pub &'a D);
+
+impl<D: DistanceProvider + ?Sized> DistanceProvider for SymmetricDistances<'_, D> {
+    fn dimension(&self) -> usize {
+        self.0.dimension()
+    }
+
+    #[inline]
+    fn distance(&self, i: usize, j: usize) -> f64 {
+        self.0.distance(i, j).min(self.0.distance(j, i))
+    }
+}
+
+/// Minimum spanning tree over every node but node 0, plus the two cheapest
+/// edges of node 0, under costs `d(i, j) + π(i) + π(j)`.
+pub struct OneTree {
+    /// Parent of every node in the spanning tree, `None` for its root and for node 0
+    pub parent: Vec<Option<u32>>,
+    /// Endpoints of the two edges of node 0
+    pub special_edges: (u32, u32),
+    pub degrees: Vec<u32>,
+    /// Length under the penalised costs
+    pub length: f64,
+}
+
+#[inline]
+fn penalised<D: DistanceProvider + ?Sized>(distances: &D, penalties: &[f64], i: usize, j: usize) -> f64 {
+    distances.distance(i, j) + penalties[i] + penalties[j]
+}
+
+/// Builds the minimum 1-tree with Prim's algorithm in O(n²).
+/// Expects at least three nodes.
+pub fn minimum_one_tree<D: DistanceProvider + ?Sized>(distances: &D, penalties: &[f64]) -> OneTree {
+    let n = distances.dimension();
+    let mut parent: Vec<Option<u32>> = vec![None; n];
+    let mut degrees = vec![0; n];
+    let mut length = 0.0;
+
+    let root = 1;
+    let mut in_tree = vec![false; n];
+    let mut closest = vec![f64::INFINITY; n];
+    in_tree[SPECIAL_NODE] = true;
+    in_tree[root] = true;
+    for j in 2..n {
+        closest[j] = penalised(distances, penalties, root, j);
+        parent[j] = Some(root as u32);
+    }
+    for _ in 2..n {
+        let mut next = 0;
+        let mut next_cost = f64::INFINITY;
+        for j in 2..n {
+            if !in_tree[j] && closest[j] <= next_cost {
+                next = j;
+                next_cost = closest[j];
+            }
+        }
+        in_tree[next] = true;
+        length += next_cost;
+        degrees[next] += 1;
+        degrees[parent[next].unwrap() as usize] += 1;
+        for j in 2..n {
+            if !in_tree[j] {
+                let cost = penalised(distances, penalties, next, j);
+                if cost < closest[j] {
+                    closest[j] = cost;
+                    parent[j] = Some(next as u32);
+                }
+            }
+        }
+    }
+
+    let mut cheapest = (f64::INFINITY, 0);
+    let mut second_cheapest = (f64::INFINITY, 0);
+    for j in 1..n {
+        let cost = penalised(distances, penalties, SPECIAL_NODE, j);
+        if cost < cheapest.0 {
+            second_cheapest = cheapest;
+            cheapest = (cost, j);
+        } else if cost < second_cheapest.0 {
+            second_cheapest = (cost, j);
+        }
+    }
+    length += cheapest.0 + second_cheapest.0;
+    degrees[SPECIAL_NODE] = 2;
+    degrees[cheapest.1] += 1;
+    degrees[second_cheapest.1] += 1;
+
+    OneTree {
+        parent,
+        special_edges: (cheapest.1 as u32, second_cheapest.1 as u32),
+        degrees,
+        length,
+    }
+}
+
+/// Held-Karp subgradient ascent: raises the penalties of nodes with degree
+/// above two and lowers the others until the 1-tree looks like a tour.
+/// Returns the penalties of the best 1-tree found and its lower bound
+/// `L(T) - 2 Σ π`. Expects a symmetric cost function and at least three nodes.
+pub fn subgradient_ascent<D: DistanceProvider + ?Sized>(distances: &D, max_iterations: usize) -> (Vec<f64>, f64) {
+    let n = distances.dimension();
+    let mut penalties = vec![0.0; n];
+    let mut tree = minimum_one_tree(distances, &penalties);
+    let mut best_penalties = penalties.clone();
+    let mut best_bound = tree.length;
+
+    // Step sizes start at a fraction of the average edge, double while the
+    // bound keeps improving and then halve at the end of every period
+    let mut step = 0.01 * tree.length.abs().max(1.0) / n as f64;
+    let mut period = (n / 2).max(1);
+    let mut period_iteration = 0;
+    let mut initial_phase = true;
+    let mut previous_gradient = vec![0.0; n];
+
+    for _ in 0..max_iterations {
+        let gradient: Vec<f64> = tree.degrees.iter().map(|&degree| degree as f64 - 2.0).collect();
+        if gradient.iter().all(|&value| value == 0.0) {
+            // The 1-tree is a tour, so the bound is optimal
+            break;
+        }
+        for ((penalty, &value), &previous_value) in penalties.iter_mut().zip(&gradient).zip(&previous_gradient) {
+            *penalty += step * (0.7 * value + 0.3 * previous_value);
+        }
+        previous_gradient = gradient;
+
+        tree = minimum_one_tree(distances, &penalties);
+        let bound = tree.length - 2.0 * penalties.iter().sum::<f64>();
+        let improved = bound > best_bound;
+        if improved {
+            best_bound = bound;
+            best_penalties.clone_from(&penalties);
+            if initial_phase {
+                step *= 2.0;
+            }
+        } else {
+            initial_phase = false;
+        }
+
+        period_iteration += 1;
+        if period_iteration >= period {
+            period_iteration = 0;
+            period = if improved { period * 2 } else { period / 2 };
+            step /= 2.0;
+            if period == 0 || step < 1e-9 {
+                break;
+            }
+        }
+    }
+    (best_penalties, best_bound)
+}
+
+/// For every node, the `k` others with the smallest alpha-nearness: the
+/// increase in length of the minimum 1-tree forced to contain their edge
+/// (Helsgaun, 2000). Ties are broken by distance. Expects a symmetric cost
+/// function and at least three nodes.
+pub fn alpha_nearest<D: DistanceProvider + Sync + ?Sized>(distances: &D, penalties: &[f64], k: usize) -> Vec<Vec<u32>> {
+    let n = distances.dimension();
+    let k = k.min(n - 1);
+    let tree = minimum_one_tree(distances, penalties);
+
+    let mut tree_neighbours: Vec<Vec<u32>> = vec![Vec::new(); n];
+    for (node, parent) in tree.parent.iter().enumerate() {
+        if let Some(parent) = parent {
+            tree_neighbours[node].push(*parent);
+            tree_neighbours[*parent as usize].push(node as u32);
+        }
+    }
+    let (first, second) = tree.special_edges;
+    // Adding an edge at node 0 replaces the more expensive of its two edges
+    let special_replaced = penalised(distances, penalties, SPECIAL_NODE, first as usize)
+        .max(penalised(distances, penalties, SPECIAL_NODE, second as usize));
+
+    (0..n).into_par_iter()
+        .map(|node| {
+            // beta[j] is the most expensive edge on the tree path from node to j,
+            // the one an edge (node, j) would replace
+            let mut beta = vec![f64::NEG_INFINITY; n];
+            if node != SPECIAL_NODE {
+                let mut stack = vec![node];
+                let mut visited = vec![false; n];
+                visited[node] = true;
+                while let Some(current) = stack.pop() {
+                    for &next in &tree_neighbours[current] {
+                        let next = next as usize;
+                        if !visited[next] {
+                            visited[next] = true;
+                            beta[next] = beta[current].max(penalised(distances, penalties, current, next));
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+
+            let mut alphas: Vec<(f64, f64, u32)> = (0..n)
+                .filter(|&other| other != node)
+                .map(|other| {
+                    let cost = penalised(distances, penalties, node, other);
+                    let alpha = if node == SPECIAL_NODE || other == SPECIAL_NODE {
+                        let endpoint = if node == SPECIAL_NODE { other } else { node } as u32;
+                        if endpoint == first || endpoint == second { 0.0 } else { cost - special_replaced }
+                    } else {
+                        cost - beta[other]
+                    };
+                    (alpha, distances.distance(node, other), other as u32)
+                })
+                .collect();
+            let by_alpha = |a: &(f64, f64, u32), b: &(f64, f64, u32)| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1));
+            if k < alphas.len() {
+                alphas.select_nth_unstable_by(k, by_alpha);
+                alphas.truncate(k);
+            }
+            alphas.sort_by(by_alpha);
+            alphas.into_iter().map(|(_, _, other)| other).collect()
+        })
+        .collect()
+}
@@ -0,0 +1,188 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::io::BufRead;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::data::error::InstanceError;
+use crate::data::instance::{Instance, LoadOptions};
+use crate::data::matrix::DistanceMatrix;
+use crate::data::node_ids::NodeIds;
+
+/// Weighted graph of a road network, read from an edge list.
+#[derive(Clone, Debug)]
+pub struct RoadNetwork {
+    // Outgoing (neighbour, weight) arcs of every node, by node index
+    adjacency: Vec<Vec<(u32, f64)>>,
+    indices: HashMap<usize, u32>,
+}
+
+// Dijkstra queue entry, ordered so that the binary heap pops the closest node first
+#[derive(PartialEq)]
+struct QueueEntry {
+    distance: f64,
+    node: u32,
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl RoadNetwork {
+    /// Reads `from to weight` lines, separated by whitespace or commas.
+    /// Blank lines and lines starting with `#` or `%` are skipped. Edges of
+    /// undirected networks can be travelled both ways, one-way streets need `directed`.
+    pub fn from_edge_list<P: AsRef<Path>>(path: P, directed: bool) -> Result<RoadNetwork, InstanceError> {
+        let path = path.as_ref();
+        let reader = Instance::open(path)?;
+        let mut network = RoadNetwork { adjacency: Vec::new(), indices: HashMap::new() };
+
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('%') {
+                continue;
+            }
+            let invalid_edge = || InstanceError::InvalidEdgeWeight {
+                line: line_number + 1,
+                content: line.clone(),
+            };
+            let parts: Vec<&str> = trimmed
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|part| !part.is_empty())
+                .collect();
+            if parts.len() != 3 {
+                return Err(invalid_edge());
+            }
+            let from = parts[0].parse::<usize>().map_err(|_| invalid_edge())?;
+            let to = parts[1].parse::<usize>().map_err(|_| invalid_edge())?;
+            let weight = parts[2].parse::<f64>().map_err(|_| invalid_edge())?;
+            // Dijkstra needs non-negative weights
+            if weight < 0.0 || weight.is_nan() {
+                return Err(invalid_edge());
+            }
+            let (from, to) = (network.node(from), network.node(to));
+            network.adjacency[from as usize].push((to, weight));
+            if !directed {
+                network.adjacency[to as usize].push((from, weight));
+            }
+        }
+        Ok(network)
+    }
+
+    // Index of a node id, registering ids seen for the first time
+    fn node(&mut self, id: usize) -> u32 {
+        let next = self.adjacency.len() as u32;
+        let index = *self.indices.entry(id).or_insert(next);
+        if index == next {
+            self.adjacency.push(Vec::new());
+        }
+        index
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Lengths of the shortest paths from `source` (a node index) to every
+    /// node, infinite for unreachable ones.
+    fn shortest_paths(&self, source: u32) -> Vec<f64> {
+        let mut distances = vec![f64::INFINITY; self.adjacency.len()];
+        let mut queue = BinaryHeap::new();
+        distances[source as usize] = 0.0;
+        queue.push(QueueEntry { distance: 0.0, node: source });
+        while let Some(QueueEntry { distance, node }) = queue.pop() {
+            if distance > distances[node as usize] {
+                continue;
+            }
+            for &(next, weight) in &self.adjacency[node as usize] {
+                let next_distance = distance + weight;
+                if next_distance < distances[next as usize] {
+                    distances[next as usize] = next_distance;
+                    queue.push(QueueEntry { distance: next_distance, node: next });
+                }
+            }
+        }
+        distances
+    }
+
+    /// Instance over the `terminals` (node ids of the network) whose distances
+    /// are shortest path lengths, running Dijkstra from every terminal.
+    /// The cities keep the terminal ids. Directed networks give asymmetric instances.
+    pub fn terminal_instance(&self, name: &str, terminals: &[usize], options: &LoadOptions) -> Result<Instance, InstanceError> {
+        let mut terminals = terminals.to_vec();
+        terminals.sort_unstable();
+        let node_ids = NodeIds::from_sorted_ids(terminals.clone())?;
+        let sources: Vec<u32> = terminals.iter()
+            .map(|id| self.indices.get(id).copied().ok_or(InstanceError::MissingNode(*id)))
+            .collect::<Result<_, _>>()?;
+
+        let rows: Vec<Vec<f64>> = sources.par_iter()
+            .map(|&source| {
+                let distances = self.shortest_paths(source);
+                sources.iter().map(|&target| distances[target as usize]).collect()
+            })
+            .collect();
+
+        let mut matrix = DistanceMatrix::new(sources.len(), false, options.precision);
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &distance) in row.iter().enumerate() {
+                if distance.is_infinite() {
+                    return Err(InstanceError::Unreachable { from: terminals[i], to: terminals[j] });
+                }
+                matrix.set(i, j, options.rounding.apply(distance));
+            }
+        }
+        let mut instance = Instance::from_distance_matrix(name, matrix, options.rounding);
+        instance.set_node_ids(node_ids);
+        Ok(instance)
+    }
+}
+
+/// Reads the node ids of a terminals file, separated by whitespace or commas.
+pub fn read_terminals<P: AsRef<Path>>(path: P) -> Result<Vec<usize>, InstanceError> {
+    let path = path.as_ref();
+    let reader = Instance::open(path)?;
+    let mut terminals = Vec::new();
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })?;
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        for part in line.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+            let id = part.parse::<usize>().map_err(|_| InstanceError::InvalidFile {
+                path: path.to_path_buf(),
+                message: format!("line {}: invalid node id {:?}", line_number + 1, part),
+            })?;
+            terminals.push(id);
+        }
+    }
+    Ok(terminals)
+}
+
+/// Loads a road network and a terminals file into an instance named after the terminals file.
+pub fn from_files<P: AsRef<Path>>(
+    edge_list_path: P,
+    terminals_path: P,
+    directed: bool,
+    options: &LoadOptions,
+) -> Result<Instance, InstanceError> {
+    let network = RoadNetwork::from_edge_list(edge_list_path, directed)?;
+    let terminals = read_terminals(&terminals_path)?;
+    let name = terminals_path.as_ref()
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    network.terminal_instance(&name, &terminals, options)
+}
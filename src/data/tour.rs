@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+
+use crate::data::error::InstanceError;
+use crate::data::instance::Instance;
+use crate::data::node_ids::NodeIds;
+
+/// Reads the TOUR_SECTION of a TSPLIB `.tour` file as 0-based cities,
+/// translating node ids with `node_ids`. Every city must appear exactly once.
+pub fn read_tour<P: AsRef<Path>>(path: P, node_ids: &NodeIds) -> Result<Vec<u32>, InstanceError> {
+    let path = path.as_ref();
+    let dimension = node_ids.len();
+    let reader = Instance::open(path)?;
+    let mut tour = Vec::new();
+    let mut visited = vec![false; dimension];
+    let mut is_reading_tour = false;
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| InstanceError::Io { path: path.to_path_buf(), source })?;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.first() == Some(&"TOUR_SECTION") {
+            is_reading_tour = true;
+            continue;
+        }
+        if !is_reading_tour {
+            continue;
+        }
+        let invalid_entry = || InstanceError::InvalidTourEntry {
+            line: line_number + 1,
+            content: line.clone(),
+        };
+        // Several node ids may share a line
+        for part in parts {
+            let id = part.parse::<i64>().map_err(|_| invalid_entry())?;
+            if id == -1 {
+                is_reading_tour = false;
+                break;
+            }
+            let city = usize::try_from(id).ok()
+                .and_then(|id| node_ids.index(id))
+                .ok_or_else(invalid_entry)?;
+            if visited[city] {
+                return Err(invalid_entry());
+            }
+            visited[city] = true;
+            tour.push(city as u32);
+        }
+    }
+    if tour.len() != dimension {
+        return Err(InstanceError::DimensionMismatch { expected: dimension, found: tour.len() });
+    }
+    Ok(tour)
+}
+
+/// Writes a 0-based tour as a TSPLIB `.tour` file, using the original node ids.
+pub fn write_tour<P: AsRef<Path>>(
+    path: P,
+    name: &str,
+    comment: Option<&str>,
+    tour: &[u32],
+    node_ids: &NodeIds,
+) -> Result<(), InstanceError> {
+    let path = path.as_ref();
+    let to_error = |source| InstanceError::Io { path: path.to_path_buf(), source };
+    let mut writer = BufWriter::new(File::create(path).map_err(to_error)?);
+
+    writeln!(writer, "NAME : {}", name).map_err(to_error)?;
+    if let Some(comment) = comment {
+        writeln!(writer, "COMMENT : {}", comment).map_err(to_error)?;
+    }
+    writeln!(writer, "TYPE : TOUR").map_err(to_error)?;
+    writeln!(writer, "DIMENSION : {}", tour.len()).map_err(to_error)?;
+    writeln!(writer, "TOUR_SECTION").map_err(to_error)?;
+    for city in tour {
+        writeln!(writer, "{}", node_ids.id(*city as usize)).map_err(to_error)?;
+    }
+    writeln!(writer, "-1").map_err(to_error)?;
+    writeln!(writer, "EOF").map_err(to_error)?;
+    writer.flush().map_err(to_error)
+}
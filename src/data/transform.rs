@@ -0,0 +1,203 @@
+use std::sync::{Arc, OnceLock};
+
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::SeedableRng;
+
+use crate::data::distances::{DistanceProvider, Distances};
+use crate::data::error::InstanceError;
+use crate::data::instance::{Instance, LoadOptions};
+use crate::data::matrix::{DistanceMatrix, MatrixPrecision};
+use crate::data::node_ids::NodeIds;
+use crate::data::tsplib::{EdgeWeightFormat, EdgeWeightType};
+
+// Instances derived from another one. Cities are renumbered 1..=n in their new
+// order, and the optimal tour, published optimum and best known length are dropped.
+// Selections leaving fewer than `MIN_CITIES` cities give no instance, as there
+// is no tour to search for.
+const MIN_CITIES: usize = 3;
+
+impl Instance {
+    /// Instance made of the given cities (0-based indices), in that order,
+    /// `None` when there are fewer than three of them.
+    pub fn sub_instance(&self, name: &str, cities: &[u32]) -> Option<Instance> {
+        if cities.len() < MIN_CITIES {
+            return None;
+        }
+        let city_coords = self.city_coords.as_ref().map(|coords| select(coords, cities));
+        let city_z_coords = self.city_z_coords.as_ref().map(|z_coords| select(z_coords, cities));
+
+        let distances = match self.distances() {
+            Distances::Lazy(_) => {
+                let coords = city_coords.as_ref().expect("lazily computed distances come from coordinates");
+                Instance::_coordinate_distances(coords, city_z_coords.as_deref(), self.edge_weight_type, &self.derived_options())
+            }
+            Distances::Matrix(matrix) => {
+                let mut sub_matrix = DistanceMatrix::new(cities.len(), matrix.is_triangular(), matrix.precision());
+                for (i, &from) in cities.iter().enumerate() {
+                    for (j, &to) in cities.iter().enumerate() {
+                        if !matrix.is_triangular() || j <= i {
+                            sub_matrix.set(i, j, matrix.get(from as usize, to as usize));
+                        }
+                    }
+                }
+                Distances::Matrix(sub_matrix)
+            }
+        };
+        Some(self.derived(name, distances, city_coords, city_z_coords))
+    }
+
+    /// The first `k` cities, `None` when fewer than three.
+    pub fn first_k(&self, k: usize) -> Option<Instance> {
+        let k = k.min(self.dimension());
+        let cities: Vec<u32> = (0..k as u32).collect();
+        self.sub_instance(&format!("{}-first{}", self.name, k), &cities)
+    }
+
+    /// `k` cities drawn at random without replacement, kept in their original
+    /// order, `None` when fewer than three.
+    pub fn random_k(&self, k: usize, seed: u64) -> Option<Instance> {
+        let k = k.min(self.dimension());
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut cities: Vec<u32> = index::sample(&mut rng, self.dimension(), k)
+            .into_iter()
+            .map(|city| city as u32)
+            .collect();
+        cities.sort_unstable();
+        self.sub_instance(&format!("{}-random{}.{}", self.name, k, seed), &cities)
+    }
+
+    /// The cities whose coordinates lie in the window between `min` and `max`
+    /// (inclusive), `None` for instances without coordinates or when fewer
+    /// than three cities lie in the window.
+    pub fn crop(&self, min: (f64, f64), max: (f64, f64)) -> Option<Instance> {
+        let coords = self.city_coords.as_ref()?;
+        let cities: Vec<u32> = (0..coords.len() as u32)
+            .filter(|&city| {
+                let (x, y) = coords[city as usize];
+                min.0 <= x && x <= max.0 && min.1 <= y && y <= max.1
+            })
+            .collect();
+        self.sub_instance(&format!("{}-crop", self.name), &cities)
+    }
+
+    /// Coordinates (z included) multiplied by `factor`. Distances are
+    /// recomputed, or scaled and rounded again for explicit instances.
+    pub fn scaled(&self, factor: f64) -> Instance {
+        let name = format!("{}-scaled", self.name);
+        let z_coords = self.city_z_coords.as_ref()
+            .map(|z_coords| z_coords.iter().map(|z| z * factor).collect());
+        self.with_coords(&name, |(x, y)| (x * factor, y * factor), z_coords, factor)
+    }
+
+    /// Planar coordinates moved by `(dx, dy)`, which leaves distances unchanged
+    /// except for the non translation-invariant `GEO` and `HAVERSINE` types.
+    pub fn translated(&self, dx: f64, dy: f64) -> Instance {
+        let name = format!("{}-translated", self.name);
+        self.with_coords(&name, |(x, y)| (x + dx, y + dy), self.city_z_coords.clone(), 1.0)
+    }
+
+    /// Instance with the cities of `self` followed by those of `other`. Both need
+    /// coordinates with the same edge weight type, as distances between them are
+    /// recomputed; ATSP and explicit instances cannot be merged.
+    pub fn merge(&self, other: &Instance) -> Result<Instance, InstanceError> {
+        let incompatible = |reason: &str| InstanceError::IncompatibleInstances(reason.to_string());
+        if self.edge_weight_type != other.edge_weight_type {
+            return Err(incompatible("different edge weight types"));
+        }
+        if self.edge_weight_type == EdgeWeightType::Explicit {
+            return Err(incompatible("explicit distances between the instances are unknown"));
+        }
+        let (Some(coords), Some(other_coords)) = (&self.city_coords, &other.city_coords) else {
+            return Err(incompatible("both instances need coordinates"));
+        };
+        let city_coords: Vec<(f64, f64)> = coords.iter().chain(other_coords).copied().collect();
+        let city_z_coords: Option<Vec<f64>> = match (&self.city_z_coords, &other.city_z_coords) {
+            (Some(z_coords), Some(other_z_coords)) => Some(z_coords.iter().chain(other_z_coords).copied().collect()),
+            _ => None,
+        };
+        let distances = Instance::_coordinate_distances(
+            &city_coords,
+            city_z_coords.as_deref(),
+            self.edge_weight_type,
+            &self.derived_options(),
+        );
+        let name = format!("{}+{}", self.name, other.name);
+        Ok(self.derived(&name, distances, Some(city_coords), city_z_coords))
+    }
+
+    fn with_coords<F>(&self, name: &str, transform: F, city_z_coords: Option<Vec<f64>>, distance_factor: f64) -> Instance
+    where
+        F: Fn((f64, f64)) -> (f64, f64),
+    {
+        let city_coords: Option<Vec<(f64, f64)>> = self.city_coords.as_ref()
+            .map(|coords| coords.iter().map(|&point| transform(point)).collect());
+        let distances = match &city_coords {
+            Some(coords) if self.edge_weight_type != EdgeWeightType::Explicit => {
+                Instance::_coordinate_distances(coords, city_z_coords.as_deref(), self.edge_weight_type, &self.derived_options())
+            }
+            // Display coordinates only: explicit weights follow the scale
+            _ => match self.distances() {
+                Distances::Matrix(matrix) => {
+                    let mut scaled = matrix.clone();
+                    for i in 0..matrix.dimension() {
+                        for j in 0..matrix.dimension() {
+                            if !matrix.is_triangular() || j <= i {
+                                scaled.set(i, j, self.rounding.apply(matrix.get(i, j) * distance_factor));
+                            }
+                        }
+                    }
+                    Distances::Matrix(scaled)
+                }
+                Distances::Lazy(_) => unreachable!("lazily computed distances come from coordinates"),
+            },
+        };
+        self.derived(name, distances, city_coords, city_z_coords)
+    }
+
+    // Options rebuilding distances with the matrix layout of this instance, the
+    // backend being picked from the derived dimension as on load
+    fn derived_options(&self) -> LoadOptions {
+        let (precision, triangular) = match self.distances() {
+            Distances::Matrix(matrix) => (matrix.precision(), matrix.is_triangular()),
+            Distances::Lazy(_) => (MatrixPrecision::default(), false),
+        };
+        LoadOptions { rounding: self.rounding, precision, triangular, ..LoadOptions::default() }
+    }
+
+    fn derived(
+        &self,
+        name: &str,
+        distances: Distances,
+        city_coords: Option<Vec<(f64, f64)>>,
+        city_z_coords: Option<Vec<f64>>,
+    ) -> Instance {
+        let dimension = distances.dimension();
+        let mut header = self.header.clone();
+        header.name = Some(name.to_string());
+        header.dimension = Some(dimension);
+        if self.edge_weight_type == EdgeWeightType::Explicit {
+            header.edge_weight_format = Some(EdgeWeightFormat::FullMatrix);
+        }
+        Instance {
+            name: name.to_string(),
+            header,
+            node_ids: Arc::new(NodeIds::sequential(dimension)),
+            optimal_solution: None,
+            distances: Arc::new(distances),
+            city_coords,
+            city_z_coords,
+            edge_weight_type: self.edge_weight_type,
+            is_symmetric: self.is_symmetric,
+            rounding: self.rounding,
+            known_optimal_length: None,
+            best_known_length: None,
+            lower_bound: OnceLock::new(),
+            candidates: None,
+        }
+    }
+}
+
+fn select<T: Copy>(values: &[T], cities: &[u32]) -> Vec<T> {
+    cities.iter().map(|&city| values[city as usize]).collect()
+}
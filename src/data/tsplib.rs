@@ -0,0 +1,449 @@
+use std::str::FromStr;
+
+use crate::data::error::InstanceError;
+use crate::data::utils;
+
+/// `EDGE_WEIGHT_TYPE` values understood by the loader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeWeightType {
+    Explicit,
+    Euc2d,
+    Euc3d,
+    Max2d,
+    Max3d,
+    Man2d,
+    Man3d,
+    Ceil2d,
+    Geo,
+    Att,
+    /// Not part of TSPLIB: great-circle distance in metres between decimal degree coordinates
+    Haversine,
+}
+
+impl FromStr for EdgeWeightType {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "EXPLICIT" => Ok(EdgeWeightType::Explicit),
+            "EUC_2D" => Ok(EdgeWeightType::Euc2d),
+            "EUC_3D" => Ok(EdgeWeightType::Euc3d),
+            "MAX_2D" => Ok(EdgeWeightType::Max2d),
+            "MAX_3D" => Ok(EdgeWeightType::Max3d),
+            "MAN_2D" => Ok(EdgeWeightType::Man2d),
+            "MAN_3D" => Ok(EdgeWeightType::Man3d),
+            "CEIL_2D" => Ok(EdgeWeightType::Ceil2d),
+            "GEO" => Ok(EdgeWeightType::Geo),
+            "ATT" => Ok(EdgeWeightType::Att),
+            "HAVERSINE" => Ok(EdgeWeightType::Haversine),
+            _ => Err(()),
+        }
+    }
+}
+
+impl EdgeWeightType {
+    /// Name of the type in TSPLIB headers.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            EdgeWeightType::Explicit => "EXPLICIT",
+            EdgeWeightType::Euc2d => "EUC_2D",
+            EdgeWeightType::Euc3d => "EUC_3D",
+            EdgeWeightType::Max2d => "MAX_2D",
+            EdgeWeightType::Max3d => "MAX_3D",
+            EdgeWeightType::Man2d => "MAN_2D",
+            EdgeWeightType::Man3d => "MAN_3D",
+            EdgeWeightType::Ceil2d => "CEIL_2D",
+            EdgeWeightType::Geo => "GEO",
+            EdgeWeightType::Att => "ATT",
+            EdgeWeightType::Haversine => "HAVERSINE",
+        }
+    }
+
+    /// Whether node coordinates carry a third (z) component.
+    pub fn is_3d(&self) -> bool {
+        matches!(self, EdgeWeightType::Euc3d | EdgeWeightType::Max3d | EdgeWeightType::Man3d)
+    }
+
+    /// Whether computing a distance costs more than looking it up in a small
+    /// sorted list, as for the trigonometry of the geographical types.
+    pub fn is_costly(&self) -> bool {
+        matches!(self, EdgeWeightType::Geo | EdgeWeightType::Haversine)
+    }
+
+    /// Distance between two nodes, `z` being ignored by the planar types.
+    pub fn distance(&self, point1: (f64, f64, f64), point2: (f64, f64, f64)) -> f64 {
+        let planar1 = (point1.0, point1.1);
+        let planar2 = (point2.0, point2.1);
+        match self {
+            EdgeWeightType::Euc2d => utils::euclidean_distance(planar1, planar2),
+            EdgeWeightType::Euc3d => utils::euclidean_distance_3d(point1, point2),
+            EdgeWeightType::Max2d => utils::maximum_distance(planar1, planar2),
+            EdgeWeightType::Max3d => utils::maximum_distance_3d(point1, point2),
+            EdgeWeightType::Man2d => utils::manhattan_distance(planar1, planar2),
+            EdgeWeightType::Man3d => utils::manhattan_distance_3d(point1, point2),
+            EdgeWeightType::Ceil2d => utils::ceil_distance(planar1, planar2),
+            EdgeWeightType::Geo => utils::geo_distance(planar1, planar2),
+            EdgeWeightType::Att => utils::att_distance(planar1, planar2),
+            EdgeWeightType::Haversine => utils::haversine_distance(planar1, planar2),
+            EdgeWeightType::Explicit => unreachable!("EXPLICIT weights are not derived from coordinates"),
+        }
+    }
+}
+
+/// How coordinate based distances are turned into edge weights.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Raw floating point distances.
+    Exact,
+    /// Distances rounded with `nint`, as TSPLIB optima are computed.
+    #[default]
+    Nint,
+}
+
+impl RoundingMode {
+    pub fn apply(&self, distance: f64) -> f64 {
+        match self {
+            RoundingMode::Exact => distance,
+            RoundingMode::Nint => utils::nint(distance),
+        }
+    }
+}
+
+/// `EDGE_WEIGHT_FORMAT` values describing how an `EDGE_WEIGHT_SECTION` is laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeWeightFormat {
+    // Weights are computed from coordinates, there is no matrix to read
+    Function,
+    FullMatrix,
+    UpperRow,
+    LowerRow,
+    UpperDiagRow,
+    LowerDiagRow,
+    UpperCol,
+    LowerCol,
+    UpperDiagCol,
+    LowerDiagCol,
+}
+
+impl FromStr for EdgeWeightFormat {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "FUNCTION" => Ok(EdgeWeightFormat::Function),
+            "FULL_MATRIX" => Ok(EdgeWeightFormat::FullMatrix),
+            "UPPER_ROW" => Ok(EdgeWeightFormat::UpperRow),
+            "LOWER_ROW" => Ok(EdgeWeightFormat::LowerRow),
+            "UPPER_DIAG_ROW" => Ok(EdgeWeightFormat::UpperDiagRow),
+            "LOWER_DIAG_ROW" => Ok(EdgeWeightFormat::LowerDiagRow),
+            "UPPER_COL" => Ok(EdgeWeightFormat::UpperCol),
+            "LOWER_COL" => Ok(EdgeWeightFormat::LowerCol),
+            "UPPER_DIAG_COL" => Ok(EdgeWeightFormat::UpperDiagCol),
+            "LOWER_DIAG_COL" => Ok(EdgeWeightFormat::LowerDiagCol),
+            _ => Err(()),
+        }
+    }
+}
+
+impl EdgeWeightFormat {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            EdgeWeightFormat::Function => "FUNCTION",
+            EdgeWeightFormat::FullMatrix => "FULL_MATRIX",
+            EdgeWeightFormat::UpperRow => "UPPER_ROW",
+            EdgeWeightFormat::LowerRow => "LOWER_ROW",
+            EdgeWeightFormat::UpperDiagRow => "UPPER_DIAG_ROW",
+            EdgeWeightFormat::LowerDiagRow => "LOWER_DIAG_ROW",
+            EdgeWeightFormat::UpperCol => "UPPER_COL",
+            EdgeWeightFormat::LowerCol => "LOWER_COL",
+            EdgeWeightFormat::UpperDiagCol => "UPPER_DIAG_COL",
+            EdgeWeightFormat::LowerDiagCol => "LOWER_DIAG_COL",
+        }
+    }
+
+    /// Matrix cells `(row, column)` in the order their weights appear in the section.
+    pub fn entries(&self, dimension: usize) -> Vec<(usize, usize)> {
+        let n = dimension;
+        let mut entries = Vec::new();
+        for a in 0..n {
+            let range = match self {
+                EdgeWeightFormat::Function => 0..0,
+                EdgeWeightFormat::FullMatrix => 0..n,
+                EdgeWeightFormat::UpperRow | EdgeWeightFormat::LowerCol => a + 1..n,
+                EdgeWeightFormat::LowerRow | EdgeWeightFormat::UpperCol => 0..a,
+                EdgeWeightFormat::UpperDiagRow | EdgeWeightFormat::LowerDiagCol => a..n,
+                EdgeWeightFormat::LowerDiagRow | EdgeWeightFormat::UpperDiagCol => 0..a + 1,
+            };
+            for b in range {
+                // Column-wise formats walk the transposed triangle
+                match self {
+                    EdgeWeightFormat::UpperCol
+                    | EdgeWeightFormat::LowerCol
+                    | EdgeWeightFormat::UpperDiagCol
+                    | EdgeWeightFormat::LowerDiagCol => entries.push((b, a)),
+                    _ => entries.push((a, b)),
+                }
+            }
+        }
+        entries
+    }
+
+    /// Whether only one triangle is given and has to be mirrored.
+    pub fn is_triangular(&self) -> bool {
+        !matches!(self, EdgeWeightFormat::Function | EdgeWeightFormat::FullMatrix)
+    }
+}
+
+/// `TYPE` of the data in a TSPLIB file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProblemType {
+    Tsp,
+    Atsp,
+    Sop,
+    Hcp,
+    Cvrp,
+    Tour,
+}
+
+impl FromStr for ProblemType {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "TSP" => Ok(ProblemType::Tsp),
+            "ATSP" => Ok(ProblemType::Atsp),
+            "SOP" => Ok(ProblemType::Sop),
+            "HCP" => Ok(ProblemType::Hcp),
+            "CVRP" => Ok(ProblemType::Cvrp),
+            "TOUR" => Ok(ProblemType::Tour),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ProblemType {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            ProblemType::Tsp => "TSP",
+            ProblemType::Atsp => "ATSP",
+            ProblemType::Sop => "SOP",
+            ProblemType::Hcp => "HCP",
+            ProblemType::Cvrp => "CVRP",
+            ProblemType::Tour => "TOUR",
+        }
+    }
+}
+
+/// `EDGE_DATA_FORMAT` values, used by graph problems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeDataFormat {
+    EdgeList,
+    AdjList,
+}
+
+impl FromStr for EdgeDataFormat {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "EDGE_LIST" => Ok(EdgeDataFormat::EdgeList),
+            "ADJ_LIST" => Ok(EdgeDataFormat::AdjList),
+            _ => Err(()),
+        }
+    }
+}
+
+impl EdgeDataFormat {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            EdgeDataFormat::EdgeList => "EDGE_LIST",
+            EdgeDataFormat::AdjList => "ADJ_LIST",
+        }
+    }
+}
+
+/// `NODE_COORD_TYPE` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeCoordType {
+    TwoD,
+    ThreeD,
+    NoCoords,
+}
+
+impl FromStr for NodeCoordType {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "TWOD_COORDS" => Ok(NodeCoordType::TwoD),
+            "THREED_COORDS" => Ok(NodeCoordType::ThreeD),
+            "NO_COORDS" => Ok(NodeCoordType::NoCoords),
+            _ => Err(()),
+        }
+    }
+}
+
+impl NodeCoordType {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            NodeCoordType::TwoD => "TWOD_COORDS",
+            NodeCoordType::ThreeD => "THREED_COORDS",
+            NodeCoordType::NoCoords => "NO_COORDS",
+        }
+    }
+}
+
+/// `DISPLAY_DATA_TYPE` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayDataType {
+    Coord,
+    TwoD,
+    NoDisplay,
+}
+
+impl FromStr for DisplayDataType {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "COORD_DISPLAY" => Ok(DisplayDataType::Coord),
+            "TWOD_DISPLAY" => Ok(DisplayDataType::TwoD),
+            "NO_DISPLAY" => Ok(DisplayDataType::NoDisplay),
+            _ => Err(()),
+        }
+    }
+}
+
+impl DisplayDataType {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            DisplayDataType::Coord => "COORD_DISPLAY",
+            DisplayDataType::TwoD => "TWOD_DISPLAY",
+            DisplayDataType::NoDisplay => "NO_DISPLAY",
+        }
+    }
+}
+
+/// Specification part of a TSPLIB file, i.e. every `KEYWORD : value` line
+/// before the data sections.
+#[derive(Clone, Debug, Default)]
+pub struct TsplibHeader {
+    pub name: Option<String>,
+    pub problem_type: Option<ProblemType>,
+    // COMMENT may be given several times
+    pub comments: Vec<String>,
+    pub dimension: Option<usize>,
+    pub capacity: Option<u64>,
+    pub edge_weight_type: Option<EdgeWeightType>,
+    pub edge_weight_format: Option<EdgeWeightFormat>,
+    pub edge_data_format: Option<EdgeDataFormat>,
+    pub node_coord_type: Option<NodeCoordType>,
+    pub display_data_type: Option<DisplayDataType>,
+    // Keywords outside the TSPLIB specification, in file order
+    pub other: Vec<(String, String)>,
+}
+
+impl TsplibHeader {
+    /// `(keyword, value)` pairs in specification order, the inverse of `set`.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        let mut push = |keyword: &str, value: Option<String>| {
+            if let Some(value) = value {
+                entries.push((keyword.to_string(), value));
+            }
+        };
+        push("NAME", self.name.clone());
+        push("TYPE", self.problem_type.map(|value| value.keyword().to_string()));
+        for comment in &self.comments {
+            push("COMMENT", Some(comment.clone()));
+        }
+        push("DIMENSION", self.dimension.map(|value| value.to_string()));
+        push("CAPACITY", self.capacity.map(|value| value.to_string()));
+        push("EDGE_WEIGHT_TYPE", self.edge_weight_type.map(|value| value.keyword().to_string()));
+        push("EDGE_WEIGHT_FORMAT", self.edge_weight_format.map(|value| value.keyword().to_string()));
+        push("EDGE_DATA_FORMAT", self.edge_data_format.map(|value| value.keyword().to_string()));
+        push("NODE_COORD_TYPE", self.node_coord_type.map(|value| value.keyword().to_string()));
+        push("DISPLAY_DATA_TYPE", self.display_data_type.map(|value| value.keyword().to_string()));
+        for (keyword, value) in &self.other {
+            push(keyword, Some(value.clone()));
+        }
+        entries
+    }
+
+    /// Records a `keyword : value` line found at `line`.
+    pub fn set(&mut self, keyword: &str, value: &str, line: usize) -> Result<(), InstanceError> {
+        let invalid_header = || InstanceError::InvalidHeader {
+            line,
+            keyword: keyword.to_string(),
+            value: value.to_string(),
+        };
+        match keyword {
+            "NAME" => self.name = Some(value.to_string()),
+            "TYPE" => self.problem_type = Some(value.parse().map_err(|_| invalid_header())?),
+            "COMMENT" => self.comments.push(value.to_string()),
+            "DIMENSION" => self.dimension = Some(value.parse().map_err(|_| invalid_header())?),
+            "CAPACITY" => self.capacity = Some(value.parse().map_err(|_| invalid_header())?),
+            "EDGE_WEIGHT_TYPE" => {
+                let parsed = value.parse::<EdgeWeightType>()
+                    .map_err(|_| InstanceError::UnsupportedEdgeWeightType(value.to_string()))?;
+                self.edge_weight_type = Some(parsed);
+            }
+            "EDGE_WEIGHT_FORMAT" => {
+                let parsed = value.parse::<EdgeWeightFormat>()
+                    .map_err(|_| InstanceError::UnsupportedEdgeWeightFormat(value.to_string()))?;
+                self.edge_weight_format = Some(parsed);
+            }
+            "EDGE_DATA_FORMAT" => self.edge_data_format = Some(value.parse().map_err(|_| invalid_header())?),
+            "NODE_COORD_TYPE" => self.node_coord_type = Some(value.parse().map_err(|_| invalid_header())?),
+            "DISPLAY_DATA_TYPE" => self.display_data_type = Some(value.parse().map_err(|_| invalid_header())?),
+            _ => self.other.push((keyword.to_string(), value.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Published optimal tour lengths of the TSPLIB instances, symmetric ones first.
+const KNOWN_OPTIMA: &[(&str, f64)] = &[
+    ("a280", 2579.0), ("ali535", 202339.0), ("att48", 10628.0), ("att532", 27686.0),
+    ("bayg29", 1610.0), ("bays29", 2020.0), ("berlin52", 7542.0), ("bier127", 118282.0),
+    ("brazil58", 25395.0), ("brd14051", 469385.0), ("brg180", 1950.0), ("burma14", 3323.0),
+    ("ch130", 6110.0), ("ch150", 6528.0), ("d198", 15780.0), ("d493", 35002.0),
+    ("d657", 48912.0), ("d1291", 50801.0), ("d1655", 62128.0), ("d2103", 80450.0),
+    ("d15112", 1573084.0), ("d18512", 645238.0), ("dantzig42", 699.0), ("dsj1000", 18659688.0),
+    ("eil51", 426.0), ("eil76", 538.0), ("eil101", 629.0), ("fl417", 11861.0),
+    ("fl1400", 20127.0), ("fl1577", 22249.0), ("fl3795", 28772.0), ("fnl4461", 182566.0),
+    ("fri26", 937.0), ("gil262", 2378.0), ("gr17", 2085.0), ("gr21", 2707.0),
+    ("gr24", 1272.0), ("gr48", 5046.0), ("gr96", 55209.0), ("gr120", 6942.0),
+    ("gr137", 69853.0), ("gr202", 40160.0), ("gr229", 134602.0), ("gr431", 171414.0),
+    ("gr666", 294358.0), ("hk48", 11461.0), ("kroA100", 21282.0), ("kroB100", 22141.0),
+    ("kroC100", 20749.0), ("kroD100", 21294.0), ("kroE100", 22068.0), ("kroA150", 26524.0),
+    ("kroB150", 26130.0), ("kroA200", 29368.0), ("kroB200", 29437.0), ("lin105", 14379.0),
+    ("lin318", 42029.0), ("linhp318", 41345.0), ("nrw1379", 56638.0), ("p654", 34643.0),
+    ("pa561", 2763.0), ("pcb442", 50778.0), ("pcb1173", 56892.0), ("pcb3038", 137694.0),
+    ("pla7397", 23260728.0), ("pla33810", 66048945.0), ("pla85900", 142382641.0), ("pr76", 108159.0),
+    ("pr107", 44303.0), ("pr124", 59030.0), ("pr136", 96772.0), ("pr144", 58537.0),
+    ("pr152", 73682.0), ("pr226", 80369.0), ("pr264", 49135.0), ("pr299", 48191.0),
+    ("pr439", 107217.0), ("pr1002", 259045.0), ("pr2392", 378032.0), ("rat99", 1211.0),
+    ("rat195", 2323.0), ("rat575", 6773.0), ("rat783", 8806.0), ("rd100", 7910.0),
+    ("rd400", 15281.0), ("rl1304", 252948.0), ("rl1323", 270199.0), ("rl1889", 316536.0),
+    ("rl5915", 565530.0), ("rl5934", 556045.0), ("rl11849", 923288.0), ("si175", 21407.0),
+    ("si535", 48450.0), ("si1032", 92650.0), ("st70", 675.0), ("swiss42", 1273.0),
+    ("ts225", 126643.0), ("tsp225", 3916.0), ("u159", 42080.0), ("u574", 36905.0),
+    ("u724", 41910.0), ("u1060", 224094.0), ("u1432", 152970.0), ("u1817", 57201.0),
+    ("u2152", 64253.0), ("u2319", 234256.0), ("ulysses16", 6859.0), ("ulysses22", 7013.0),
+    ("usa13509", 19982859.0), ("vm1084", 239297.0), ("vm1748", 336556.0),
+    ("br17", 39.0), ("ft53", 6905.0), ("ft70", 38673.0), ("ftv33", 1286.0),
+    ("ftv35", 1473.0), ("ftv38", 1530.0), ("ftv44", 1613.0), ("ftv47", 1776.0),
+    ("ftv55", 1608.0), ("ftv64", 1839.0), ("ftv70", 1950.0), ("ftv90", 1579.0),
+    ("ftv100", 1788.0), ("ftv110", 1958.0), ("ftv120", 2166.0), ("ftv130", 2307.0),
+    ("ftv140", 2420.0), ("ftv150", 2611.0), ("ftv160", 2683.0), ("ftv170", 2755.0),
+    ("kro124p", 36230.0), ("p43", 5620.0), ("rbg323", 1326.0), ("rbg358", 1163.0),
+    ("rbg403", 2465.0), ("rbg443", 2720.0), ("ry48p", 14422.0),
+];
+
+/// Optimal tour length published for a TSPLIB instance, if it is a known one.
+pub fn known_optimal_length(name: &str) -> Option<f64> {
+    KNOWN_OPTIMA.iter()
+        .find(|(known_name, _)| *known_name == name)
+        .map(|&(_, length)| length)
+}
@@ -1,6 +1,14 @@
-extern crate rand;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+// Distance functions follow the TSPLIB 95 specification, which deliberately truncates pi for GEO
+#[allow(clippy::approx_constant)]
+const GEO_PI: f64 = 3.141592;
+const GEO_EARTH_RADIUS: f64 = 6378.388;
+// Mean earth radius used for great-circle distances on real coordinates
+const EARTH_RADIUS_METRES: f64 = 6_371_000.0;
+
+/// Nearest integer as defined by TSPLIB (`(int) (x + 0.5)`).
+pub fn nint(x: f64) -> f64 {
+    (x + 0.5).floor()
+}
 
 pub fn euclidean_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
     let dx = point1.0 - point2.0;
@@ -8,22 +16,68 @@ pub fn euclidean_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
     (dx * dx + dy * dy).sqrt()
 }
 
-fn generate_random_unique_pairs(n: i32) -> Vec<Vec<(i32, i32)>> {
-    let mut rng = rand::thread_rng();
-    let mut numbers: Vec<i32> = (0..n).collect();
-    let mut pairs: Vec<Vec<(i32, i32)>> = Vec::new();
-    numbers.shuffle(&mut rng);
-    for i in 0..(n as usize) {
-        for j in (i + 1)..(n as usize) {
-            let mut inner_pairs: Vec<(i32, i32)> = Vec::new();
-            inner_pairs.push((numbers[i], numbers[j]));        }
-    }
-    pairs
-}
-
-fn permute_pairs(pairs: Vec<Vec<(i32, i32)>>) -> Vec<Vec<(i32, i32)>> {
-    let mut rng = thread_rng();
-    let mut shuffled_pairs = pairs.clone();
-    shuffled_pairs.shuffle(&mut rng);
-    shuffled_pairs
-}
\ No newline at end of file
+pub fn euclidean_distance_3d(point1: (f64, f64, f64), point2: (f64, f64, f64)) -> f64 {
+    let dx = point1.0 - point2.0;
+    let dy = point1.1 - point2.1;
+    let dz = point1.2 - point2.2;
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+pub fn manhattan_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
+    (point1.0 - point2.0).abs() + (point1.1 - point2.1).abs()
+}
+
+pub fn manhattan_distance_3d(point1: (f64, f64, f64), point2: (f64, f64, f64)) -> f64 {
+    (point1.0 - point2.0).abs() + (point1.1 - point2.1).abs() + (point1.2 - point2.2).abs()
+}
+
+pub fn maximum_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
+    (point1.0 - point2.0).abs().max((point1.1 - point2.1).abs())
+}
+
+pub fn maximum_distance_3d(point1: (f64, f64, f64), point2: (f64, f64, f64)) -> f64 {
+    (point1.0 - point2.0).abs()
+        .max((point1.1 - point2.1).abs())
+        .max((point1.2 - point2.2).abs())
+}
+
+/// Euclidean distance rounded up to the next integer (`CEIL_2D`).
+pub fn ceil_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
+    euclidean_distance(point1, point2).ceil()
+}
+
+// Converts a TSPLIB DDD.MM coordinate (degrees and minutes) into radians
+fn geo_radians(coordinate: f64) -> f64 {
+    let degrees = coordinate.trunc();
+    let minutes = coordinate - degrees;
+    GEO_PI * (degrees + 5.0 * minutes / 3.0) / 180.0
+}
+
+/// Geographical distance in kilometres between (latitude, longitude) points given as DDD.MM (`GEO`).
+pub fn geo_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
+    let (latitude1, longitude1) = (geo_radians(point1.0), geo_radians(point1.1));
+    let (latitude2, longitude2) = (geo_radians(point2.0), geo_radians(point2.1));
+    let q1 = (longitude1 - longitude2).cos();
+    let q2 = (latitude1 - latitude2).cos();
+    let q3 = (latitude1 + latitude2).cos();
+    (GEO_EARTH_RADIUS * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0).trunc()
+}
+
+/// Pseudo-Euclidean distance used by the att48/att532 instances (`ATT`).
+pub fn att_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
+    let dx = point1.0 - point2.0;
+    let dy = point1.1 - point2.1;
+    let r = ((dx * dx + dy * dy) / 10.0).sqrt();
+    let t = nint(r);
+    if t < r { t + 1.0 } else { t }
+}
+
+/// Great-circle distance in metres between (latitude, longitude) points
+/// given in decimal degrees, as found in real-world data.
+pub fn haversine_distance(point1: (f64, f64), point2: (f64, f64)) -> f64 {
+    let (latitude1, longitude1) = (point1.0.to_radians(), point1.1.to_radians());
+    let (latitude2, longitude2) = (point2.0.to_radians(), point2.1.to_radians());
+    let half_chord = ((latitude2 - latitude1) / 2.0).sin().powi(2)
+        + latitude1.cos() * latitude2.cos() * ((longitude2 - longitude1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METRES * half_chord.sqrt().min(1.0).asin()
+}
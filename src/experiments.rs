@@ -1,55 +1,111 @@
 use std::fs::File;
 use std::io::BufWriter;
+use std::path::Path;
 use csv::Writer;
 use rayon::prelude::*;
 use std::sync::{Arc, Mutex};
 
-use crate::algorithms::utils::Algorithm;
-use crate::data::instance::Instance;
-use crate::algorithms::random_search::RandomSearch;
-use crate::algorithms::random_walk::RandomWalk;
+use crate::algorithms::utils::{Algorithm, MoveType};
+use crate::data::instance::{Instance, LoadOptions};
 use crate::algorithms::steepest::SteepestLocalSearch;
 use crate::algorithms::greedy::GreedyLocalSearch;
-use crate::algorithms::heuristic::HeuristicBaseline;
+// Used by the commented out creators below
+#[allow(unused_imports)]
+use crate::algorithms::{heuristic::HeuristicBaseline, random_search::RandomSearch, random_walk::RandomWalk};
 use crate::algorithms::sim_annealing::SimmulatedAnnealing;
 use crate::algorithms::tabu_search::TabuSearch;
 
+type AlgorithmCreator = Box<dyn Fn (Instance, i32, i32, Option<Vec<u32>>, u64) -> Box<dyn Algorithm> + Send + Sync>;
 
-pub fn run_comparison_experiment(num_runs: usize, file_path: &str) {
-    let algorithms: Vec<Box<dyn Fn (Instance, i32, i32, Option<Vec<i32>>) -> Box<dyn Algorithm> + Send + Sync>> = vec![
-        // Box::new(|instance, steps, logging_interval, initial_solution| 
-        //     Box::new(HeuristicBaseline::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        // Box::new(|instance, steps, logging_interval, initial_solution| 
-        //     Box::new(RandomSearch::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        // Box::new(|instance, steps, logging_interval, initial_solution| 
-        //     Box::new(RandomWalk::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        // Box::new(|instance, steps, logging_interval, initial_solution| 
-        //     Box::new(GreedyLocalSearch::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        // Box::new(|instance, steps, logging_interval, initial_solution| 
-        //     Box::new(SteepestLocalSearch::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        // Box::new(|instance, steps, logging_interval, initial_solution| 
-        //     Box::new(SimmulatedAnnealing::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>)
-        Box::new(|instance, steps, logging_interval, initial_solution| 
-            Box::new(SimmulatedAnnealing::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        Box::new(|instance, steps, logging_interval, initial_solution| 
-            Box::new(TabuSearch::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>)
+/// Base seed of the experiments, from which the seed of every run is derived.
+pub const DEFAULT_EXPERIMENT_SEED: u64 = 42;
+
+// SplitMix64 finaliser, spreading close inputs over unrelated seeds
+fn mix(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// Seed of one run, fixed by the base seed, the instance name, the position
+/// of the algorithm in the experiment and the run number.
+pub fn run_seed(base_seed: u64, instance_name: &str, algorithm_index: usize, run: usize) -> u64 {
+    let instance_hash = instance_name.bytes()
+        .fold(base_seed, |hash, byte| mix(hash ^ byte as u64));
+    mix(mix(instance_hash ^ algorithm_index as u64) ^ run as u64)
+}
+
+
+// Tours are kept 0-based in memory but reported with the node ids of the instance file
+fn tour_column(instance: &Instance, tour: &[u32]) -> String {
+    let node_ids: Vec<usize> = tour.iter().map(|&city| instance.node_id(city as usize)).collect();
+    format!("{:?}", node_ids)
+}
+
+// Optimal tour and its length as CSV columns, left empty when the instance has no optimal tour
+fn optimal_solution_columns(instance: &Instance) -> (String, String) {
+    match &instance.optimal_solution {
+        Some(optimal_solution) => (
+            tour_column(instance, optimal_solution),
+            instance.get_solution_distance(optimal_solution).to_string(),
+        ),
+        None => (String::new(), String::new()),
+    }
+}
+
+pub fn run_comparison_experiment(num_runs: usize, data_root: &Path, options: &LoadOptions, file_path: &str, base_seed: u64) {
+    let algorithms: Vec<AlgorithmCreator> = vec![
+        // Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+        //     Box::new(HeuristicBaseline::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        // Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+        //     Box::new(RandomSearch::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        // Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+        //     Box::new(RandomWalk::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        // Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+        //     Box::new(GreedyLocalSearch::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        // Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+        //     Box::new(SteepestLocalSearch::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        // Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+        //     Box::new(SimmulatedAnnealing::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>)
+        Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+            Box::new(SimmulatedAnnealing::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+            Box::new(TabuSearch::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+            Box::new(SimmulatedAnnealing::new(instance, steps, logging_interval, initial_solution, seed)
+                .with_move_type(MoveType::TwoOpt)) as Box<dyn Algorithm>),
+        Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+            Box::new(TabuSearch::new(instance, steps, logging_interval, initial_solution, seed)
+                .with_move_type(MoveType::TwoOpt)) as Box<dyn Algorithm>)
     ];
-    let instance_names: Vec<&str> = vec!["berlin52", "ch130", "eil76", "lin105", "tsp225", "kroA100", "kroC100", "kroD100"];
+    let instance_names: [&str; 8] = ["berlin52", "ch130", "eil76", "lin105", "tsp225", "kroA100", "kroC100", "kroD100"];
     let writer = Arc::new(Mutex::new(Writer::from_writer(BufWriter::new(File::create(file_path).expect("Cannot create file")))));
 
     writer.lock().unwrap().write_record(
-        &["Instance", "Algorithm", "Run", "Step", "Evaluated Solutions", "Elapsed Time (Microseconds)", "Solution", "Solution Score", "Solution Distance", "Optimal Solution", "Optimal Solution Distance"]
+        ["Instance", "Algorithm", "Run", "Step", "Evaluated Solutions", "Elapsed Time (Microseconds)", "Solution", "Solution Score", "Solution Distance", "Optimal Solution", "Optimal Solution Distance", "Score Reference", "Reference Distance", "Lower Bound Gap", "Seed"]
     ).expect("Error writing header");
         
     for instance_name in instance_names{
         println!("Running experiments on {:?} instance", instance_name);
-        let instance = Instance::new(instance_name);
-        algorithms.iter().for_each(|algo_creator| {
+        let instance = match Instance::from_data_root_with_options(data_root, instance_name, options) {
+            Ok(instance) => instance,
+            Err(error) => {
+                eprintln!("Skipping {:?} instance: {}", instance_name, error);
+                continue;
+            }
+        };
+        if let Err(error) = instance.validate_optimal_solution() {
+            eprintln!("Warning for {:?} instance: {}", instance_name, error);
+        }
+        // Computed once here so every cloned instance shares it
+        instance.lower_bound();
+        algorithms.iter().enumerate().for_each(|(algorithm_index, algo_creator)| {
             (1..num_runs).into_par_iter().for_each(|run| {
-                let optimal_solution = &instance.optimal_solution;
-                let optimal_solution_str =  format!("{:?}", optimal_solution);
-                let optimal_solution_distance = &instance.get_solution_distance(&optimal_solution);
-                let mut algorithm = algo_creator(instance.clone(), 1000, 5, None);
+                let seed = run_seed(base_seed, instance_name, algorithm_index, run);
+                let (optimal_solution_str, optimal_solution_distance) = optimal_solution_columns(&instance);
+                let (score_reference, reference_distance) = instance.score_reference();
+                let mut algorithm = algo_creator(instance.clone(), 1000, 5, None, seed);
                 let result = algorithm.execute();            
                 for (step, stats) in result.iter() {
                     let record = vec![
@@ -59,11 +115,15 @@ pub fn run_comparison_experiment(num_runs: usize, file_path: &str) {
                         step.to_string(),
                         stats.evaluated_solutions.to_string(),
                         stats.elapsed_time.to_string(),
-                        format!("{:?}", stats.solution),
+                        tour_column(&instance, &stats.solution),
                         stats.solution_score.to_string(),
                         stats.solution_distance.to_string(),
                         optimal_solution_str.clone(),
-                        optimal_solution_distance.to_string(),
+                        optimal_solution_distance.clone(),
+                        score_reference.name().to_string(),
+                        reference_distance.to_string(),
+                        stats.lower_bound_gap.to_string(),
+                        seed.to_string(),
                     ];
                     let mut guard = writer.lock().unwrap();
                     guard.write_record(&record).expect("Error writing record");
@@ -74,47 +134,62 @@ pub fn run_comparison_experiment(num_runs: usize, file_path: &str) {
 }
 
 
-pub fn run_initial_solution_experiment(num_runs: usize, file_path: &str) {
+pub fn run_initial_solution_experiment(num_runs: usize, data_root: &Path, options: &LoadOptions, file_path: &str, base_seed: u64) {
 
-    let algorithms: Vec<Box<dyn Fn (Instance, i32, i32, Option<Vec<i32>>) -> Box<dyn Algorithm>>> = vec![
-        Box::new(|instance, steps, logging_interval, initial_solution| 
-            Box::new(GreedyLocalSearch::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>),
-        Box::new(|instance, steps, logging_interval, initial_solution| 
-            Box::new(SteepestLocalSearch::new(instance, steps, logging_interval, initial_solution)) as Box<dyn Algorithm>)
+    let algorithms: Vec<AlgorithmCreator> = vec![
+        Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+            Box::new(GreedyLocalSearch::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>),
+        Box::new(|instance, steps, logging_interval, initial_solution, seed| 
+            Box::new(SteepestLocalSearch::new(instance, steps, logging_interval, initial_solution, seed)) as Box<dyn Algorithm>)
     ];
-    let instance_names: Vec<&str> = vec!["berlin52", "eil76"];
+    let instance_names: [&str; 2] = ["berlin52", "eil76"];
     let mut writer = Writer::from_writer(BufWriter::new(File::create(file_path).expect("Cannot create file")));
 
     writer.write_record(
-        &["Instance", "Algorithm", "Run", "Step", "Evaluated Solutions", "Elapsed Time (Microseconds)", "Solution", "Solution Score", "Solution Distance", "Optimal Solution", "Optimal Solution Distance"]
+        ["Instance", "Algorithm", "Run", "Step", "Evaluated Solutions", "Elapsed Time (Microseconds)", "Solution", "Solution Score", "Solution Distance", "Optimal Solution", "Optimal Solution Distance", "Score Reference", "Reference Distance", "Lower Bound Gap", "Seed"]
     ).expect("Error writing header");
         
     for instance_name in instance_names{
         println!("Running experiments on {:?} instance", instance_name);
-        let instance = Instance::new(instance_name);
-        let optimal_solution = &instance.optimal_solution;
-        let optimal_solution_str =  format!("{:?}", optimal_solution);
-        let optimal_solution_distance = instance.get_solution_distance(&optimal_solution);
+        let instance = match Instance::from_data_root_with_options(data_root, instance_name, options) {
+            Ok(instance) => instance,
+            Err(error) => {
+                eprintln!("Skipping {:?} instance: {}", instance_name, error);
+                continue;
+            }
+        };
+        if let Err(error) = instance.validate_optimal_solution() {
+            eprintln!("Warning for {:?} instance: {}", instance_name, error);
+        }
+        // Computed once here so every cloned instance shares it
+        instance.lower_bound();
+        let (optimal_solution_str, optimal_solution_distance) = optimal_solution_columns(&instance);
+        let (score_reference, reference_distance) = instance.score_reference();
 
-        for algo_creator in algorithms.iter() {
+        for (algorithm_index, algo_creator) in algorithms.iter().enumerate() {
             for run in 1..num_runs {
+                let seed = run_seed(base_seed, instance_name, algorithm_index, run);
             //  let mut heur = HeuristicBaseline::new(instance.clone(), 1000, 10000, None);
             //     let heur_result = heur.execute();   
-                let mut algorithm = algo_creator(instance.clone(), 1000, 10000, None);
+                let mut algorithm = algo_creator(instance.clone(), 1000, 10000, None, seed);
                 let result = algorithm.execute();            
                 for (step, stats) in result.iter() {
-                    writer.write_record(&[
+                    writer.write_record([
                         instance_name,
-                        &algorithm.get_name(),
+                        algorithm.get_name(),
                         &run.to_string(),
                         &step.to_string(),
                         &stats.evaluated_solutions.to_string(),
                         &stats.elapsed_time.to_string(),
-                        &format!("{:?}", stats.solution),
+                        &tour_column(&instance, &stats.solution),
                         &stats.solution_score.to_string(),
                         &stats.solution_distance.to_string(),
                         &optimal_solution_str,
-                        &optimal_solution_distance.to_string(),
+                        &optimal_solution_distance,
+                        score_reference.name(),
+                        &reference_distance.to_string(),
+                        &stats.lower_bound_gap.to_string(),
+                        &seed.to_string(),
                     ]).expect("Error writing record");
                 }
             }
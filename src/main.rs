@@ -1,12 +1,20 @@
-mod data;
-mod algorithms;
-mod experiments;
-use experiments::run_comparison_experiment;
+use std::env;
+
+use codebase::data::instance::{cache_root, data_root, LoadOptions};
+use codebase::experiments::{run_comparison_experiment, DEFAULT_EXPERIMENT_SEED};
 
 fn main() {
+    let results_path = env::args().nth(1).unwrap_or_else(|| "results_SA_TA.csv".to_string());
+    let seed = env::args().nth(2)
+        .map(|seed| seed.parse().expect("The seed must be an unsigned integer"))
+        .unwrap_or(DEFAULT_EXPERIMENT_SEED);
+    // Instances are parsed once and read back from the cache by later runs
+    let options = LoadOptions { cache_dir: Some(cache_root()), ..LoadOptions::default() };
     run_comparison_experiment(
-        100, 
-        "/Users/dawid/Private/School/Sem 1/Biologically Inspired Algorithms/codebase/results_SA_TA.csv"
+        100,
+        &data_root(),
+        &options,
+        &results_path,
+        seed
     )
 }
-